@@ -0,0 +1,113 @@
+// Plays minesweeper by itself and reports how often a simple strategy wins, to help choose the
+// default board sizes and mine counts.  Only the game logic is built, without Telegram.
+//
+//     cargo run --release --no-default-features --bin simulate -- [rows] [columns] [mines] [generation] [kernel] [edges]
+//         [games=...] [seed=...]
+//
+// Without a mine count, a range of densities is compared instead.
+
+use std::env;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use minesweeper_bot::coord::Coord;
+use minesweeper_bot::minesweeper::field::{Field, Generation, Multiplicity, State};
+use minesweeper_bot::minesweeper::solver;
+use minesweeper_bot::minesweeper::topology::{Edges, Topology};
+
+// mine densities compared when no mine count is given, in percent
+const DENSITIES: [u32; 6] = [5, 10, 15, 20, 25, 30];
+
+#[derive(Default)]
+struct Results {
+    games: u32,
+    won: u32,
+    bbbv: u32,
+    // clicks on cells that could not be proven safe, the first click excluded
+    guesses: u32,
+}
+
+impl Results {
+    fn report(&self, field: &Field) -> String {
+        let games = self.games.max(1) as f64;
+        format!("{:>4} mines ({:>4.1}%): won {:>5.1}%, 3BV {:>5.1}, {:>4.2} guesses per game",
+            field.mines, field.mines as f64 * 100.0 / field.size.size() as f64,
+            self.won as f64 * 100.0 / games, self.bbbv as f64 / games, self.guesses as f64 / games)
+    }
+}
+
+// Starts in the middle, then uncovers every cell that can be deduced to be safe, guessing the cell
+// least likely to be a mine when there is none.  Flags are of no use to the solver.
+fn play(field: &mut Field, generation: Generation, rng: &mut ChaCha8Rng, results: &mut Results) {
+    let start = Coord((field.size.0 / 2) as _, (field.size.1 / 2) as _);
+    field.initialize(start, generation, rng);
+    field.uncover(start);
+    let solved = |field: &Field| field.stats.uncovered_blank + field.mines == field.size.size();
+    while field.stats.exploded == 0 && !solved(field) {
+        let analysis = match solver::analyze(field) {
+            Some(analysis) => analysis,
+            None => break,
+        };
+        if analysis.safe.is_empty() {
+            match analysis.safest() {
+                Some((coord, _)) => field.uncover(coord),
+                None => break,
+            }
+            results.guesses += 1;
+        } else {
+            for coord in analysis.safe {
+                // an earlier one may have opened up this one already
+                if field[coord].state == State::Covered {
+                    field.uncover(coord);
+                }
+            }
+        }
+    }
+    results.games += 1;
+    results.won += solved(field) as u32;
+    results.bbbv += field.bbbv().1;
+}
+
+fn main() {
+    let mut args = Vec::new();
+    let mut generation = Generation::Random;
+    let mut topology = Topology::default();
+    let mut games = 1000;
+    let mut seed = rand::random();
+    for arg in env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("games=") {
+            games = value.parse().unwrap_or(games);
+        } else if let Some(value) = arg.strip_prefix("seed=") {
+            seed = value.parse().unwrap_or(seed);
+        } else if let Ok(policy) = arg.parse() {
+            generation = policy;
+        } else if let Ok(kernel) = arg.parse() {
+            topology.kernel = kernel;
+        } else if let Ok(edges) = arg.parse::<Edges>() {
+            topology.edges = edges;
+        } else if let Ok(num) = arg.parse() {
+            if args.len() < 3 { args.push(num); }
+        } else {
+            eprintln!("unknown option {}", arg);
+        }
+    }
+    let rows = args.first().copied().unwrap_or(10);
+    let columns = args.get(1).copied().unwrap_or(8);
+    let mine_counts = match args.get(2) {
+        Some(&mines) => vec![mines],
+        None => DENSITIES.iter().map(|density| rows * columns * density / 100).collect(),
+    };
+
+    println!("{} games each on {} x {}, seed {}", games, rows, columns, seed);
+    // the same seed plays the same games
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    for mines in mine_counts {
+        let mut results = Results::default();
+        let field = Field::new(rows, columns, mines, topology, Multiplicity::Single);
+        for _ in 0..games {
+            play(&mut field.clone(), generation, &mut rng, &mut results);
+        }
+        println!("{}", results.report(&field));
+    }
+}
@@ -4,12 +4,17 @@ use itertools::Itertools;
 use telegram_bot::{User, InlineKeyboardMarkup};
 
 use crate::game::{Coord, Game, InteractResult};
-use crate::grid_game::{GameState, GridGame};
+use crate::grid_game::{GameState, GridGame, HINT};
 
 // Wraps a cooperative game and implements interaction stats
 pub struct CoopGame<T: GridGame> {
     game: T,
     interactions: HashMap<String, u32>,
+    // mistakes survived, by whoever made them
+    mistakes: HashMap<String, u32>,
+    // cells cleared, by whoever cleared them
+    cleared: HashMap<String, u32>,
+    hints: u32,
 }
 
 impl<T: GridGame> CoopGame<T> {
@@ -19,56 +24,126 @@ impl<T: GridGame> CoopGame<T> {
         (Self {
             game,
             interactions: HashMap::new(),
+            mistakes: HashMap::new(),
+            cleared: HashMap::new(),
+            hints: 0,
         }, text, inline_keyboard)
     }
+
+    fn summarize(&self, username: &str) -> String {
+        let state = self.game.get_state();
+        let mut largest_count = 0;
+        let mut top_contributor = "";
+        let mut summary = self.game.get_text() + "\n";
+        summary += self.interactions.iter()
+            .map(|(name, &count)| {
+                if count > largest_count {
+                    largest_count = count;
+                    top_contributor = name;
+                }
+                let mut line = format!("{} - {} moves", name.as_str(), count);
+                if let Some(cleared) = self.cleared.get(name) {
+                    line += format!(", {} cells", cleared).as_str();
+                }
+                if let Some(mistakes) = self.mistakes.get(name) {
+                    line += format!(", 💥 {}", mistakes).as_str();
+                }
+                line
+            }).join("\n").as_str();
+        summary += "\n";
+        if self.hints > 0 {
+            summary += format!("💡 {} hints used\n", self.hints).as_str();
+        }
+        let &count = self.interactions.get(username).unwrap();
+        if count == largest_count {
+            // It's the top contributor acting
+            if state == GameState::Solved {
+                summary += format!("{} has won the game!", username).as_str();
+            } else {
+                summary += format!("Boom, {} is dead!", username).as_str();
+            }
+        } else if state == GameState::Solved {
+            // Someone else interfered
+            summary += format!("{} has snatched it from {}!", username, top_contributor).as_str();
+        } else {
+            summary += format!("{} has ruined it for {}!", username, top_contributor).as_str();
+        }
+        summary
+    }
 }
 
 impl<T: GridGame> Game for CoopGame<T> {
     fn interact(&mut self, coord: Coord, user: &User) -> Option<InteractResult> {
-        self.game.interact(coord).then_some({
-            let username = user.username.as_ref().unwrap_or(&user.first_name);
+        let username = user.username.as_ref().unwrap_or(&user.first_name);
+        let mistakes = self.game.mistakes();
+        let cleared = self.game.cleared();
+        self.game.interact(coord, username).then_some({
             *self.interactions.entry(username.to_owned()).or_default() += 1;
+            let cleared = self.game.cleared() - cleared;
+            if cleared > 0 {
+                *self.cleared.entry(username.to_owned()).or_default() += cleared;
+            }
+            let mistakes = self.game.mistakes() - mistakes;
+            if mistakes > 0 {
+                *self.mistakes.entry(username.to_owned()).or_default() += mistakes;
+            }
 
             let keyboard_markup = self.game.to_inline_keyboard();
-            let state = self.game.get_state();
-            if state == GameState::Normal {
+            if self.game.get_state() == GameState::Normal {
+                let mut text = self.game.get_text();
+                if mistakes > 0 {
+                    text += format!("\n💥 {} hit a mine!", username).as_str();
+                }
                 InteractResult {
-                    update_text: Some(self.game.get_text()),
+                    update_text: Some(text),
                     update_board: Some(keyboard_markup),
                     game_end: false,
+                    new_game: None,
+                    reply: None,
                 }
             } else {
-                let mut largest_count = 0;
-                let mut top_contributor = "";
-                let mut summary = self.interactions.iter()
-                    .map(|(name, &count)| {
-                        if count > largest_count {
-                            largest_count = count;
-                            top_contributor = name;
-                        }
-                        format!("{} - {} moves", name.as_str(), count)
-                    }).join("\n") + "\n";
-                let &count = self.interactions.get(username).unwrap();
-                if count == largest_count {
-                    // It's the top contributor acting
-                    if state == GameState::Solved {
-                        summary += format!("{} has won the game!", username).as_str();
-                    } else {
-                        summary += format!("Boom, {} is dead!", username).as_str();
-                    }
-                } else if state == GameState::Solved {
-                    // Someone else interfered
-                    summary += format!("{} has snatched it from {}!", username, top_contributor).as_str();
-                } else {
-                    summary += format!("{} has ruined it for {}!", username, top_contributor).as_str();
-                }
-
                 InteractResult {
-                    update_text: Some(summary),
+                    update_text: Some(self.summarize(username)),
                     update_board: Some(keyboard_markup),
                     game_end: true,
+                    new_game: None,
+                    reply: None,
                 }
             }
         })
     }
+
+    fn tick(&mut self) -> Option<InteractResult> {
+        // nobody is to blame when the game ends by itself
+        self.game.tick().then(|| InteractResult {
+            update_text: Some(self.game.get_text()),
+            update_board: Some(self.game.to_inline_keyboard()),
+            game_end: self.game.get_state() != GameState::Normal,
+            new_game: None,
+            reply: None,
+        })
+    }
+
+    fn replay(&self) -> Option<Box<dyn Game>> {
+        self.game.replay()
+    }
+
+    fn press(&mut self, button: &str, _user: &User) -> Option<InteractResult> {
+        let changed = if button == HINT {
+            let hinted = self.game.hint();
+            self.hints += hinted as u32;
+            hinted
+        } else {
+            self.game.press(button)
+        };
+        changed.then(|| {
+            InteractResult {
+                update_text: Some(self.game.get_text()),
+                update_board: Some(self.game.to_inline_keyboard()),
+                game_end: false,
+                new_game: None,
+                reply: None,
+            }
+        })
+    }
 }
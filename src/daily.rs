@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use telegram_bot::{ChatId, InlineKeyboardMarkup, User, UserId};
+
+use crate::game::{Coord, Game, InteractResult};
+use crate::grid_game::{GameState, GridGame};
+use crate::minesweeper::game::Game as MinesweeperGame;
+
+const ROWS: u32 = 10;
+const COLUMNS: u32 = 8;
+const MINES: u32 = 12;
+const LEADERBOARD_LENGTH: usize = 5;
+
+// days since 1970-01-01 UTC
+pub fn today() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() / 86400
+}
+
+// converts days since 1970-01-01 to a date, see http://howardhinnant.github.io/date_algorithms.html
+fn date(day: u64) -> String {
+    let z = day + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + (m <= 2) as u64;
+    format!("{}-{:02}-{:02}", y, m, d)
+}
+
+struct Entry {
+    chat: ChatId,
+    name: String,
+    time: Duration,
+    clicks: u32,
+}
+
+// Results of the daily challenges.  Everyone gets one attempt per day, since the board is the same
+// for all and known after the first try.
+#[derive(Default)]
+pub struct Leaderboard {
+    played: HashSet<(u64, UserId)>,
+    solved: HashMap<u64, Vec<Entry>>,
+}
+
+impl Leaderboard {
+    fn record(&mut self, day: u64, entry: Entry) {
+        let entries = self.solved.entry(day).or_default();
+        let position = entries.iter()
+            .position(|e| (e.time, e.clicks) > (entry.time, entry.clicks))
+            .unwrap_or(entries.len());
+        entries.insert(position, entry);
+    }
+
+    pub fn get_text(&self, day: u64, chat: ChatId) -> String {
+        let entries = self.solved.get(&day).map_or(&[][..], |e| &e[..]);
+        let format_entries = |entries: Vec<&Entry>| if entries.is_empty() {
+            "No one has solved it yet.\n".to_owned()
+        } else {
+            entries.iter()
+                .take(LEADERBOARD_LENGTH)
+                .enumerate()
+                .map(|(i, e)| format!("{}. {} - {:.1}s, {} clicks\n",
+                    i + 1, e.name, e.time.as_secs_f64(), e.clicks))
+                .collect()
+        };
+        format!("Daily challenge {}\n\nIn this chat:\n{}\nEverywhere:\n{}", date(day),
+            format_entries(entries.iter().filter(|e| e.chat == chat).collect()),
+            format_entries(entries.iter().collect()))
+    }
+}
+
+// One player's attempt at the daily challenge
+pub struct DailyGame {
+    game: MinesweeperGame,
+    day: u64,
+    chat: ChatId,
+    player: (UserId, String),
+    started: Instant,
+    clicks: u32,
+    leaderboard: Arc<Mutex<Leaderboard>>,
+}
+
+impl DailyGame {
+    // returns None if the user has already played today
+    pub fn create(
+        leaderboard: &Arc<Mutex<Leaderboard>>, chat: ChatId, user: &User
+    ) -> Option<(Self, String, InlineKeyboardMarkup)> {
+        let day = today();
+        if !leaderboard.lock().unwrap().played.insert((day, user.id)) {
+            return None;
+        }
+        let game = Self {
+            game: MinesweeperGame::with_opening(ROWS, COLUMNS, MINES, day),
+            day,
+            chat,
+            player: (user.id, user.username.to_owned().unwrap_or_else(|| user.first_name.to_owned())),
+            started: Instant::now(),
+            clicks: 0,
+            leaderboard: leaderboard.clone(),
+        };
+        let text = game.get_text();
+        let inline_keyboard = game.game.to_inline_keyboard();
+        Some((game, text, inline_keyboard))
+    }
+
+    fn get_text(&self) -> String {
+        format!("Daily challenge {} for {}\n{}", date(self.day), self.player.1, self.game.get_text())
+    }
+}
+
+impl Game for DailyGame {
+    fn interact(&mut self, coord: Coord, user: &User) -> Option<InteractResult> {
+        (user.id == self.player.0 && self.game.interact(coord, &self.player.1)).then(|| {
+            self.clicks += 1;
+            let state = self.game.get_state();
+            let mut text = self.get_text();
+            if state == GameState::Solved {
+                let time = self.started.elapsed();
+                text += format!("\nSolved in {:.1}s with {} clicks\n\n", time.as_secs_f64(), self.clicks).as_str();
+                let mut leaderboard = self.leaderboard.lock().unwrap();
+                leaderboard.record(self.day, Entry {
+                    chat: self.chat,
+                    name: self.player.1.to_owned(),
+                    time,
+                    clicks: self.clicks,
+                });
+                text += leaderboard.get_text(self.day, self.chat).as_str();
+            }
+            InteractResult {
+                update_text: Some(text),
+                update_board: Some(self.game.to_inline_keyboard()),
+                game_end: state != GameState::Normal,
+                new_game: None,
+                reply: None,
+            }
+        })
+    }
+
+    fn replay(&self) -> Option<Box<dyn Game>> {
+        self.game.replay()
+    }
+
+    fn press(&mut self, button: &str, user: &User) -> Option<InteractResult> {
+        (user.id == self.player.0 && self.game.press(button)).then(|| InteractResult {
+            update_text: None,
+            update_board: Some(self.game.to_inline_keyboard()),
+            game_end: false,
+            new_game: None,
+            reply: None,
+        })
+    }
+}
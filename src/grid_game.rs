@@ -1,6 +1,6 @@
 use telegram_bot::*;
 
-use crate::game::Coord;
+use crate::game::{Coord, Game};
 
 #[derive(Eq, PartialEq)]
 pub enum GameState {
@@ -9,9 +9,18 @@ pub enum GameState {
     GameOver,
 }
 
-pub trait GridGame {
+// callback data of the hint button
+pub const HINT: &str = "hint";
+
+pub trait GridGame: Send {
     fn get_state(&self) -> GameState;
     fn get_text(&self) -> String;
     fn to_inline_keyboard(&self) -> InlineKeyboardMarkup;
-    fn interact(&mut self, coord: Coord) -> bool;   // return true if the board changed
+    fn interact(&mut self, coord: Coord, user: &str) -> bool;   // return true if the board changed
+    fn press(&mut self, _button: &str) -> bool { false }    // return true if the game changed
+    fn hint(&mut self) -> bool { false }    // return true if a hint was given
+    fn tick(&mut self) -> bool { false }    // called every second, return true if the game changed
+    fn replay(&self) -> Option<Box<dyn Game>> { None }  // what is left of the game once it has ended
+    fn mistakes(&self) -> u32 { 0 }     // how many mistakes were survived, such as mines hit
+    fn cleared(&self) -> u32 { 0 }      // how many cells have been cleared, flood fills included
 }
@@ -0,0 +1,19 @@
+#![feature(bool_to_option)]
+#![feature(option_result_contains)]
+
+// The games, shared by the bot in main.rs and the tools in src/bin.  Everything that talks to
+// Telegram is behind the "bot" feature, so that the tools also build without it.
+pub mod coord;
+pub mod minesweeper;
+#[cfg(feature = "bot")]
+pub mod coop_game;
+#[cfg(feature = "bot")]
+pub mod daily;
+#[cfg(feature = "bot")]
+pub mod game;
+#[cfg(feature = "bot")]
+pub mod grid_game;
+#[cfg(feature = "bot")]
+pub mod othello;
+#[cfg(feature = "bot")]
+pub mod race;
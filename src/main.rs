@@ -1,10 +1,11 @@
 #![feature(box_syntax)]
 #![feature(bool_to_option)]
-#![feature(option_result_contains)]
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use hyper::client::{Client, HttpConnector};
@@ -15,16 +16,24 @@ use telegram_bot::connector::Connector;
 use telegram_bot::connector::hyper::{default_connector, HyperConnector};
 use thiserror::Error;
 
-use crate::coop_game::CoopGame;
-use crate::game::Game;
-use crate::minesweeper::game::Game as MinesweeperGame;
-use othello::game::Game as OthelloGame;
+use minesweeper_bot::coop_game::CoopGame;
+use minesweeper_bot::daily::{self, DailyGame, Leaderboard};
+use minesweeper_bot::game::{Game, InteractResult};
+use minesweeper_bot::grid_game::HINT;
+use minesweeper_bot::minesweeper::editor::Editor;
+use minesweeper_bot::minesweeper::flags::Game as FlagsGame;
+use minesweeper_bot::minesweeper::game::{Game as MinesweeperGame, OptionError};
+use minesweeper_bot::minesweeper::infinite::Game as InfiniteGame;
+use minesweeper_bot::othello::game::Game as OthelloGame;
+use minesweeper_bot::race::{Race, RaceBoard};
 
-mod minesweeper;
-mod grid_game;
-mod game;
-mod coop_game;
-mod othello;
+// in bytes, which is stricter than the limit of 4096 characters
+const MESSAGE_LENGTH: usize = 4096;
+// how many replays are kept, and for how long, the oldest making way first
+const MAX_REPLAYS: usize = 1000;
+const REPLAY_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+// how many ticks the last edit of a game is tried again for, should it fail
+const EDIT_RETRIES: u32 = 60;
 
 #[derive(Error, Debug)]
 enum Error {
@@ -32,12 +41,16 @@ enum Error {
     BotError(#[from] telegram_bot::Error),
     #[error("no command given or command not understood")]
     NoCommand,
+    #[error("options of a new game not accepted")]
+    InvalidOptions(#[from] OptionError),
     #[error("received invalid coordinates")]
     InvalidCoord,
     #[error("message too old")]
     MessageTooOld,
     #[error("message is not a game")]
     NoSuchGame,
+    #[error("game logic panicked")]
+    GamePanicked(#[from] tokio::task::JoinError),
 }
 
 
@@ -49,15 +62,38 @@ fn parse_command<'a>(command: &'a str, bot_name: &str, is_private_chat: bool) ->
     }
 }
 
-fn create_game(data: &str, entities: &[MessageEntity], user: &User) -> Option<(Box<dyn Game>, String, InlineKeyboardMarkup)> {
+// the race to join from "/start race<id>", which is sent by following the link on its message
+fn race_id(command: &str, data: &str) -> Option<u32> {
+    if command != "/start" {
+        return None;
+    }
+    data.split_whitespace().nth(1)?.strip_prefix("race")?.parse().ok()
+}
+
+// Game logic runs on the threads for blocking work, since the solver can keep it busy for a while
+// on big boards, which would hold up everything else on the event loop
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, Error> {
+    Ok(tokio::task::spawn_blocking(f).await?)
+}
+
+fn create_game(data: &str, entities: &[MessageEntity], user: &User) -> Result<(Box<dyn Game>, String, InlineKeyboardMarkup), Error> {
     if data.starts_with("/mine") {
-        let (game, text, inline_keyboard) = CoopGame::create(MinesweeperGame::from_message(data));
-        Some((box game, text, inline_keyboard))
+        let (game, text, inline_keyboard) = CoopGame::create(MinesweeperGame::from_message(data)?);
+        Ok((box game, text, inline_keyboard))
+    } else if data.starts_with("/infinite") {
+        let (game, text, inline_keyboard) = CoopGame::create(InfiniteGame::from_message(data)?);
+        Ok((box game, text, inline_keyboard))
+    } else if data.starts_with("/edit") {
+        let (editor, text, inline_keyboard) = Editor::from_message(data, user)?;
+        Ok((box editor, text, inline_keyboard))
+    } else if data.starts_with("/flags") {
+        let (game, text, inline_keyboard) = FlagsGame::from_message(data, entities, user).ok_or(Error::NoCommand)?;
+        Ok((box game, text, inline_keyboard))
     } else if data.starts_with("/othello") {
-        let (game, text, inline_keyboard) = OthelloGame::from_message(data, entities, user)?;
-        Some((box game, text, inline_keyboard))
+        let (game, text, inline_keyboard) = OthelloGame::from_message(data, entities, user).ok_or(Error::NoCommand)?;
+        Ok((box game, text, inline_keyboard))
     } else {
-        None
+        Err(Error::NoCommand)
     }
 }
 
@@ -66,6 +102,14 @@ struct GameManager<'a> {
     api: &'a Api,
     bot_name: String,
     running_games: HashMap<(ChatId, MessageId), Box<dyn Game>>,
+    // what is left of finished games, with when they ended; kept apart as they neither tick nor
+    // count as running
+    replays: HashMap<(ChatId, MessageId), (Instant, Box<dyn Game>)>,
+    // last edits of games that failed to go through, such as when rate limited, with the tries left
+    unsent: HashMap<(ChatId, MessageId), (u32, InteractResult)>,
+    leaderboard: Arc<Mutex<Leaderboard>>,
+    // races by their id, for runners joining through the link on the race's message
+    races: HashMap<u32, Arc<Mutex<Race>>>,
 }
 
 impl<'a> GameManager<'a> {
@@ -75,9 +119,117 @@ impl<'a> GameManager<'a> {
             api,
             bot_name: me.username.unwrap(),
             running_games: HashMap::new(),
+            replays: HashMap::new(),
+            unsent: HashMap::new(),
+            leaderboard: Arc::new(Mutex::new(Leaderboard::default())),
+            races: HashMap::new(),
+        }
+    }
+
+    async fn start_game(
+        &mut self, message: &Message, game: Box<dyn Game>, text: String, inline_keyboard: InlineKeyboardMarkup
+    ) -> Result<(), Error> {
+        let mut reply = message.text_reply(text);
+        reply.reply_markup(inline_keyboard);
+        let reply = self.api.send(reply).await?;
+        if let MessageOrChannelPost::Message(reply) = reply {
+            self.running_games.insert((reply.chat.id(), reply.id), game);
+        }
+        Ok(())
+    }
+
+    // finished games make way for their replays, if they have any
+    fn end_game(&mut self, key: (ChatId, MessageId)) {
+        let game = self.running_games.remove(&key).or_else(|| self.replays.remove(&key).map(|(_, game)| game));
+        if let Some(replay) = game.and_then(|game| game.replay()) {
+            if self.replays.len() >= MAX_REPLAYS {
+                let oldest = self.replays.iter().min_by_key(|(_, &(ended, _))| ended).map(|(&key, _)| key);
+                if let Some(oldest) = oldest {
+                    self.replays.remove(&oldest);
+                }
+            }
+            self.replays.insert(key, (Instant::now(), replay));
         }
     }
 
+    // long replies are split at line breaks to stay under the length limit of messages
+    async fn send_reply(&self, message: &Message, text: String) -> Result<(), Error> {
+        let mut chunk = String::new();
+        for line in text.lines() {
+            if chunk.len() + line.len() >= MESSAGE_LENGTH {
+                self.api.send(message.text_reply(chunk)).await?;
+                chunk = String::new();
+            }
+            chunk += line;
+            chunk += "\n";
+        }
+        if !chunk.is_empty() {
+            self.api.send(message.text_reply(chunk)).await?;
+        }
+        Ok(())
+    }
+
+    // plays a game away from the event loop, and puts it back afterwards
+    async fn play<T: Send + 'static>(
+        &mut self, key: (ChatId, MessageId), f: impl FnOnce(&mut dyn Game) -> T + Send + 'static
+    ) -> Result<T, Error> {
+        let (mut game, ended) = match self.running_games.remove(&key) {
+            Some(game) => (game, None),
+            None => {
+                let (ended, game) = self.replays.remove(&key).ok_or(Error::NoSuchGame)?;
+                (game, Some(ended))
+            }
+        };
+        let (game, result) = blocking(move || {
+            let result = f(game.as_mut());
+            (game, result)
+        }).await?;
+        if let Some(ended) = ended {
+            self.replays.insert(key, (ended, game));
+        } else {
+            self.running_games.insert(key, game);
+        }
+        Ok(result)
+    }
+
+    async fn tick(&mut self) -> Result<(), Error> {
+        let mut games = std::mem::take(&mut self.running_games);
+        let (games, results) = blocking(move || {
+            let results: Vec<_> = games.iter_mut()
+                .filter_map(|(&key, game)| game.tick().map(|result| (key, result)))
+                .collect();
+            (games, results)
+        }).await?;
+        self.running_games = games;
+        let unsent = std::mem::take(&mut self.unsent)
+            .into_iter()
+            .map(|(key, (tries, result))| (key, result, tries - 1, true));
+        let results = results.into_iter().map(|(key, result)| (key, result, EDIT_RETRIES, false));
+        for ((chat, message), result, tries, retry) in unsent.chain(results) {
+            if result.game_end && !retry {
+                self.end_game((chat, message));
+            }
+            // the message would be left mid-game without the last edit, so it is tried again
+            let last = (result.game_end && tries > 0).then(|| InteractResult {
+                update_text: result.update_text.clone(),
+                update_board: result.update_board.clone(),
+                game_end: true,
+                new_game: None,
+                reply: None,
+            });
+            // one message failing to update, say as it was deleted, shouldn't hold up the others
+            if let Err(e) = result.edit(self.api, chat, message).await {
+                eprintln!("{:?}", e);
+                if let Some(last) = last {
+                    self.unsent.insert((chat, message), (tries, last));
+                }
+            }
+        }
+        self.races.retain(|_, race| !race.lock().unwrap().is_over());
+        self.replays.retain(|_, (ended, _)| ended.elapsed() < REPLAY_TIME);
+        Ok(())
+    }
+
     async fn handle_update(
         &mut self, update: Result<Update, telegram_bot::Error>
     ) -> Result<(), Error> {
@@ -99,35 +251,88 @@ impl<'a> GameManager<'a> {
                         self.api.send(message.text_reply(text)).await?;
                     } else if command == "/del" {
                         if let Some(reply_to) = message.reply_to_message {
-                            self.running_games.remove(&(reply_to.to_source_chat(), reply_to.to_message_id()))
+                            let key = (reply_to.to_source_chat(), reply_to.to_message_id());
+                            self.running_games.remove(&key)
+                                .or_else(|| self.replays.remove(&key).map(|(_, game)| game))
                                 .ok_or(Error::NoSuchGame)?;
                             self.api.send(reply_to.delete()).await?;
                         }
-                    } else if let Some((game, text, inline_keyboard)) = create_game(data, entities, &message.from) {
-                        let mut reply = message.text_reply(text);
-                        reply.reply_markup(inline_keyboard);
-                        let reply = self.api.send(reply).await?;
-                        if let MessageOrChannelPost::Message(reply) = reply {
-                            self.running_games.insert((reply.chat.id(), reply.id), game);
+                    } else if command == "/hint" {
+                        if let Some(reply_to) = message.reply_to_message {
+                            if let MessageOrChannelPost::Message(reply_to) = *reply_to {
+                                let user = message.from.clone();
+                                let result = self.play((reply_to.chat.id(), reply_to.id), move |game| {
+                                    game.press(HINT, &user)
+                                }).await?;
+                                if let Some(result) = result {
+                                    result.reply_to(self.api, &reply_to).await?;
+                                }
+                            }
+                        }
+                    } else if command == "/daily" {
+                        let chat = message.chat.id();
+                        let (leaderboard, user) = (self.leaderboard.clone(), message.from.clone());
+                        let created = blocking(move || DailyGame::create(&leaderboard, chat, &user)).await?;
+                        if let Some((game, text, inline_keyboard)) = created {
+                            self.start_game(&message, box game, text, inline_keyboard).await?;
+                        } else {
+                            let text = "You have already played today's challenge.\n\n".to_owned()
+                                + self.leaderboard.lock().unwrap().get_text(daily::today(), chat).as_str();
+                            self.api.send(message.text_reply(text)).await?;
+                        }
+                    } else if command == "/race" {
+                        let race = Race::new();
+                        let (board, text, inline_keyboard) = RaceBoard::create(&race, &self.bot_name);
+                        self.races.insert(race.lock().unwrap().id, race.clone());
+                        self.start_game(&message, box board, text, inline_keyboard).await?;
+                    } else if let Some(id) = race_id(command, data) {
+                        let (race, user) = (self.races.get(&id).cloned(), message.from.clone());
+                        let joined = blocking(move || race.and_then(|race| Race::join(&race, &user))).await?;
+                        if let Some((game, text, inline_keyboard)) = joined {
+                            self.start_game(&message, box game, text, inline_keyboard).await?;
+                        } else {
+                            let text = "You have already joined this race, or it has closed.";
+                            self.api.send(message.text_reply(text)).await?;
                         }
                     } else {
-                        self.api.send(message.text_reply("Command not understood.")).await?;
+                        match create_game(data, entities, &message.from) {
+                            Ok((game, text, inline_keyboard)) => {
+                                self.start_game(&message, game, text, inline_keyboard).await?;
+                            }
+                            Err(Error::InvalidOptions(e)) => {
+                                self.api.send(message.text_reply(e.to_string())).await?;
+                            }
+                            Err(_) => {
+                                self.api.send(message.text_reply("Command not understood.")).await?;
+                            }
+                        }
                     }
                 }
             }
             UpdateKind::CallbackQuery(query) => {
                 self.api.send(query.acknowledge()).await?;
-                let coord = query.data.ok_or(Error::InvalidCoord)?
-                    .parse().map_err(|_| Error::InvalidCoord)?;
+                let data = query.data.ok_or(Error::InvalidCoord)?;
                 let message = query.message.ok_or(Error::MessageTooOld)?;
                 if let MessageOrChannelPost::Message(message) = message {
-                    let game = self.running_games.get_mut(&(message.chat.id(), message.id))
-                        .ok_or(Error::NoSuchGame)?;
-                    let result = game.interact(coord, &query.from).unwrap_or_default();
+                    let user = query.from;
+                    let mut result = self.play((message.chat.id(), message.id), move |game| {
+                        match data.parse() {
+                            Ok(coord) => game.interact(coord, &user),
+                            Err(_) => game.press(&data, &user),
+                        }
+                    }).await?.unwrap_or_default();
                     if result.game_end {
-                        self.running_games.remove(&(message.chat.id(), message.id));
+                        self.end_game((message.chat.id(), message.id));
                     }
+                    let new_game = result.new_game.take();
+                    let reply = result.reply.take();
                     result.reply_to(self.api, &message).await?;
+                    if let Some((game, text, inline_keyboard)) = new_game {
+                        self.start_game(&message, game, text, inline_keyboard).await?;
+                    }
+                    if let Some(reply) = reply {
+                        self.send_reply(&message, reply).await?;
+                    }
                 }
             }
             _ => (),
@@ -156,9 +361,17 @@ async fn main() {
     let mut stream = api.stream();
 
     let mut manager = GameManager::new(&api).await;
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
 
-    while let Some(update) = stream.next().await {
-        if let Err(e) = manager.handle_update(update).await {
+    loop {
+        let result = tokio::select! {
+            update = stream.next() => match update {
+                Some(update) => manager.handle_update(update).await,
+                None => break,
+            },
+            _ = ticks.tick() => manager.tick().await,
+        };
+        if let Err(e) = result {
             eprintln!("{:?}", e);
         }
     }
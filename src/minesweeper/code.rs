@@ -0,0 +1,219 @@
+use crate::coord::{Coord, Size};
+use super::field::{CellValue, Field};
+use super::topology::{Edges, Kernel, Topology};
+
+// Puzzle codes are a version byte, the rows, the columns and the topology, followed by a nibble per
+// cell: the weight of its mine (with 4 standing for a negative mine) plus 8 if it starts uncovered.
+// The bytes are then written in URL-safe base64 so that codes survive being pasted around.
+const VERSION: u8 = 0;
+const UNCOVERED: u8 = 8;
+const NEGATIVE: u8 = 4;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const KERNELS: [Kernel; 5] = [Kernel::Square, Kernel::Hex, Kernel::Orthogonal, Kernel::Knight, Kernel::Extended];
+const EDGES: [Edges; 3] = [Edges::Bounded, Edges::Cylinder, Edges::Torus];
+
+// Where the mines are and which cells are uncovered from the start, enough to play a board again
+#[derive(Clone)]
+pub struct Layout {
+    pub size: Size,
+    pub topology: Topology,
+    pub mines: Vec<(Coord, i32)>,
+    pub uncovered: Vec<Coord>,
+}
+
+impl Layout {
+    pub fn of(field: &Field, uncovered: &[Coord]) -> Self {
+        let mines = field.size.valid_indices()
+            .filter_map(|c| match field[c].value {
+                CellValue::Mine(weight) => Some((c, weight)),
+                CellValue::Number(_) => None,
+            })
+            .collect();
+        Self { size: field.size, topology: field.topology, mines, uncovered: uncovered.to_vec() }
+    }
+
+    pub fn to_field(&self) -> Field {
+        Field::with_layout(self.size, self.topology, &self.mines, &self.uncovered)
+    }
+
+    pub fn encode(&self) -> String {
+        let size = self.size;
+        let mut nibbles = vec![0; size.size() as _];
+        for &(coord, weight) in &self.mines {
+            nibbles[size.index(coord)] = if weight < 0 { NEGATIVE } else { weight as u8 };
+        }
+        for &coord in &self.uncovered {
+            nibbles[size.index(coord)] |= UNCOVERED;
+        }
+        let kernel = KERNELS.iter().position(|&k| k == self.topology.kernel).unwrap();
+        let edges = EDGES.iter().position(|&e| e == self.topology.edges).unwrap();
+        let mut bytes = vec![VERSION, size.0 as u8, size.1 as u8, (kernel * EDGES.len() + edges) as u8];
+        bytes.extend(nibbles.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)));
+        to_base64(&bytes)
+    }
+
+    // Returns None for anything that isn't a valid code
+    pub fn decode(code: &str) -> Option<Self> {
+        let bytes = from_base64(code)?;
+        if bytes.len() < 4 {
+            return None;
+        }
+        let (header, cells) = bytes.split_at(4);
+        let (version, rows, columns, topology) = (header[0], header[1], header[2], header[3]);
+        let topology = Topology {
+            kernel: *KERNELS.get(topology as usize / EDGES.len())?,
+            edges: EDGES[topology as usize % EDGES.len()],
+        };
+        let size = Size(rows as _, columns as _);
+        if version != VERSION || cells.len() != size.size() as usize / 2 + size.size() as usize % 2 {
+            return None;
+        }
+        let mut nibbles = cells.iter().flat_map(|&b| vec![b >> 4, b & 15]);
+        let mut layout = Self { size, topology, mines: Vec::new(), uncovered: Vec::new() };
+        for coord in size.valid_indices() {
+            let nibble = nibbles.next()?;
+            match nibble & !UNCOVERED {
+                0 => (),
+                NEGATIVE => layout.mines.push((coord, -1)),
+                weight if weight < NEGATIVE => layout.mines.push((coord, weight as _)),
+                _ => return None,
+            }
+            if nibble & UNCOVERED != 0 {
+                layout.uncovered.push(coord);
+            }
+        }
+        layout.validate()
+    }
+
+    // A hand-made board, one line per row with '*' for a mine, '.' for a covered cell and 'o' for
+    // an uncovered one.  Spaces are ignored.
+    pub fn parse_grid(text: &str, topology: Topology) -> Option<Self> {
+        let rows: Vec<Vec<char>> = text.lines()
+            .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+            .filter(|row: &Vec<char>| !row.is_empty())
+            .collect();
+        let columns = rows.first()?.len();
+        if rows.iter().any(|row| row.len() != columns) {
+            return None;
+        }
+        let size = Size(rows.len() as _, columns as _);
+        let mut layout = Self { size, topology, mines: Vec::new(), uncovered: Vec::new() };
+        for (coord, &c) in size.valid_indices().zip(rows.iter().flatten()) {
+            match c {
+                '*' => layout.mines.push((coord, 1)),
+                'o' => layout.uncovered.push(coord),
+                '.' => (),
+                _ => return None,
+            }
+        }
+        layout.validate()
+    }
+
+    // uncovered mines, boards the field would resize and boards without anything left to clear
+    // are not playable
+    pub fn validate(self) -> Option<Self> {
+        let Size(rows, columns) = self.size;
+        let uncovered_mine = self.mines.iter().any(|(c, _)| self.uncovered.contains(c));
+        if rows < 2 || columns < 2 || self.topology.fit_rows(rows) != rows || uncovered_mine {
+            return None;
+        }
+        let field = self.to_field();
+        (field.stats.uncovered_blank + field.mines < field.size.size()).then_some(self)
+    }
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            result.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    result
+}
+
+fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let digits = text.bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|d| d as u32))
+        .collect::<Option<Vec<_>>>()?;
+    let mut result = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk.iter().enumerate().fold(0, |n, (i, &d)| n | d << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            result.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(size: Size, topology: Topology, mines: &[(Coord, i32)], uncovered: &[Coord]) -> Layout {
+        Layout { size, topology, mines: mines.to_vec(), uncovered: uncovered.to_vec() }
+    }
+
+    fn assert_same(a: &Layout, b: &Layout) {
+        assert!(a.size == b.size);
+        assert!(a.topology == b.topology);
+        assert_eq!(a.mines, b.mines);
+        assert_eq!(a.uncovered, b.uncovered);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let mines = [(Coord(0, 0), 1), (Coord(1, 2), 3), (Coord(2, 4), -1)];
+        let uncovered = [Coord(2, 0), Coord(2, 1)];
+        // an odd number of cells leaves half a byte over
+        for &size in &[Size(3, 5), Size(4, 6)] {
+            for &kernel in &KERNELS {
+                for &edges in &EDGES {
+                    let topology = Topology { kernel, edges };
+                    if topology.fit_rows(size.0) != size.0 {
+                        continue;
+                    }
+                    let original = layout(size, topology, &mines, &uncovered);
+                    let decoded = Layout::decode(&original.encode()).unwrap();
+                    assert_same(&decoded, &original);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn codes_survive_a_field() {
+        let original = layout(Size(4, 4), Topology::default(), &[(Coord(3, 3), 2)], &[Coord(0, 0)]);
+        let field = original.to_field();
+        assert_same(&Layout::of(&field, &original.uncovered), &original);
+    }
+
+    #[test]
+    fn rejects_invalid_codes() {
+        let code = layout(Size(3, 3), Topology::default(), &[(Coord(1, 1), 1)], &[]).encode();
+        assert!(Layout::decode(&code).is_some());
+        assert!(Layout::decode("").is_none());
+        assert!(Layout::decode("not a code").is_none());
+        assert!(Layout::decode(&code[..code.len() - 2]).is_none());
+        assert!(Layout::decode(&to_base64(&[VERSION + 1, 2, 2, 0, 0x10, 0])).is_none());
+        // a mine weighing 5
+        assert!(Layout::decode(&to_base64(&[VERSION, 2, 2, 0, 0x50, 0])).is_none());
+        // an uncovered mine
+        assert!(Layout::decode(&to_base64(&[VERSION, 2, 2, 0, 0x90, 0])).is_none());
+    }
+
+    #[test]
+    fn parses_grids() {
+        let parsed = Layout::parse_grid("* . .\no . .\n", Topology::default()).unwrap();
+        assert_same(&parsed, &layout(Size(2, 3), Topology::default(), &[(Coord(0, 0), 1)], &[Coord(1, 0)]));
+        assert!(Layout::parse_grid("", Topology::default()).is_none());
+        assert!(Layout::parse_grid("*..\n..", Topology::default()).is_none());
+        assert!(Layout::parse_grid("*x.\n...", Topology::default()).is_none());
+        // nothing left to clear
+        assert!(Layout::parse_grid("*o\noo", Topology::default()).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use telegram_bot::{InlineKeyboardButton, InlineKeyboardMarkup, User, UserId};
+
+use crate::coop_game::CoopGame;
+use crate::game::{Coord, Game, InteractResult, Size};
+use super::code::Layout;
+use super::field::{CellValue, State};
+use super::game::{Game as MinesweeperGame, OptionError};
+use super::topology::{Edges, Topology};
+use super::viewport::{MAX_ROWS, MAX_SIZE, max_columns, Viewport};
+
+// callback data of the buttons under the board
+const SAVE: &str = "save";
+const PUBLISH: &str = "publish";
+
+// A board laid out by hand.  Clicking a cell cycles it through covered, mine and uncovered, and
+// only the author may edit it.
+pub struct Editor {
+    layout: Layout,
+    author: UserId,
+    // the code of the layout when it was last saved
+    code: Option<String>,
+    view: Viewport,
+}
+
+impl Editor {
+    // "/edit rows columns", with a topology, or "/edit code=..." to continue with a saved board
+    pub fn from_message(data: &str, user: &User) -> Result<(Self, String, InlineKeyboardMarkup), OptionError> {
+        let mut args = Vec::new();
+        let mut topology = Topology::default();
+        let mut code = None;
+        for arg in data.split_whitespace().skip(1) {
+            if let Some(value) = arg.strip_prefix("code=") {
+                code = Some(Layout::decode(value).ok_or_else(|| OptionError::Code(value.to_owned()))?);
+            } else if let Ok(kernel) = arg.parse() {
+                topology.kernel = kernel;
+            } else if let Ok(edges) = arg.parse::<Edges>() {
+                topology.edges = edges;
+            } else if let Ok(num) = arg.parse() {
+                if args.len() < 2 { args.push(num); }
+            }
+        }
+        if code.as_ref().filter(|layout| layout.size.0 > MAX_SIZE || layout.size.1 > MAX_SIZE).is_some() {
+            return Err(OptionError::TooLarge(MAX_SIZE));
+        }
+        let layout = code.unwrap_or_else(|| {
+            let rows = args.first().copied().unwrap_or(MAX_ROWS).clamp(2, MAX_SIZE);
+            let columns = args.get(1).copied().unwrap_or_else(|| max_columns(topology.kernel)).clamp(2, MAX_SIZE);
+            Layout {
+                size: Size(topology.fit_rows(rows), columns),
+                topology,
+                mines: Vec::new(),
+                uncovered: Vec::new(),
+            }
+        });
+        let view = Viewport::new(layout.size, layout.topology.kernel);
+        let editor = Self { layout, author: user.id, code: None, view };
+        let text = editor.get_text();
+        let inline_keyboard = editor.to_inline_keyboard(true);
+        Ok((editor, text, inline_keyboard))
+    }
+
+    fn get_text(&self) -> String {
+        let size = self.layout.size;
+        let mut text = format!("Editing a {} x {} board", size.0, size.1);
+        for rule in self.layout.topology.describe() {
+            text += ", ";
+            text += rule;
+        }
+        text += format!("\n{} mines", self.layout.mines.len()).as_str();
+        if let Some(code) = &self.code {
+            text += format!("\nSaved, play it with /mine code={}", code).as_str();
+        }
+        text
+    }
+
+    fn to_inline_keyboard(&self, controls: bool) -> InlineKeyboardMarkup {
+        let field = self.layout.to_field();
+        let mut keyboard = self.view.render(|coord| match (field[coord].state, field[coord].value) {
+            (_, CellValue::Mine(_)) => "💣".to_owned(),
+            (State::Uncovered, _) if field.is_blank(coord) => " ".to_owned(),
+            (State::Uncovered, CellValue::Number(n)) => n.to_string(),
+            _ => "■".to_owned(),
+        });
+        if controls {
+            keyboard.push(vec![
+                InlineKeyboardButton::callback("💾 Save", SAVE),
+                InlineKeyboardButton::callback("📢 Publish", PUBLISH),
+            ]);
+        }
+        keyboard.into()
+    }
+}
+
+impl Game for Editor {
+    fn interact(&mut self, coord: Coord, user: &User) -> Option<InteractResult> {
+        if user.id != self.author {
+            return None;
+        }
+        let layout = &mut self.layout;
+        if let Some(i) = layout.mines.iter().position(|&(c, _)| c == coord) {
+            layout.mines.remove(i);
+            layout.uncovered.push(coord);
+        } else if let Some(i) = layout.uncovered.iter().position(|&c| c == coord) {
+            layout.uncovered.remove(i);
+        } else {
+            layout.mines.push((coord, 1));
+        }
+        self.code = None;
+        Some(InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.to_inline_keyboard(true)),
+            game_end: false,
+            new_game: None,
+            reply: None,
+        })
+    }
+
+    fn press(&mut self, button: &str, user: &User) -> Option<InteractResult> {
+        if user.id != self.author {
+            return None;
+        }
+        if self.view.pan(button) {
+            return Some(InteractResult {
+                update_text: None,
+                update_board: Some(self.to_inline_keyboard(true)),
+                game_end: false,
+                new_game: None,
+                reply: None,
+            });
+        }
+        match button {
+            SAVE => {
+                self.code = Some(self.layout.encode());
+                Some(InteractResult {
+                    update_text: Some(self.get_text()),
+                    update_board: None,
+                    game_end: false,
+                    new_game: None,
+                    reply: None,
+                })
+            }
+            PUBLISH => Some(match self.layout.clone().validate() {
+                Some(layout) => {
+                    let (game, text, inline_keyboard) = CoopGame::create(MinesweeperGame::with_layout(layout));
+                    InteractResult {
+                        update_text: Some(format!("Published, code: {}", self.layout.encode())),
+                        update_board: Some(self.to_inline_keyboard(false)),
+                        game_end: true,
+                        new_game: Some((Box::new(game), text, inline_keyboard)),
+                        reply: None,
+                    }
+                }
+                None => InteractResult {
+                    update_text: Some(self.get_text() + "\nThere has to be a safe cell left to clear."),
+                    update_board: None,
+                    game_end: false,
+                    new_game: None,
+                    reply: None,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
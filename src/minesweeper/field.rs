@@ -1,22 +1,41 @@
 use std::collections::vec_deque::VecDeque;
 use std::iter;
 use std::ops::{Index, IndexMut};
+use std::str::FromStr;
 
-use crate::game::{Coord, Size};
+use crate::coord::{Coord, Size};
+use super::solver;
+use super::topology::Topology;
 
-// In our UI there is no flagging; if a cell is numbered k and has exactly k uncovered neighbors
-// and the player decides to "uncover-around" it, then all neighbors will be uncovered as mines.
+// A flagged cell is still covered as far as the game is concerned; the flag only protects it from
+// being uncovered, and counts towards chording.  Flags carry the number of mines the player thinks
+// are under them.
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum State {
     Covered,
+    Flagged(i32),
     Uncovered,
     Exploded,
 }
 
+// A mine holds as many mines as its weight, a negative one subtracts from the numbers around it
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum CellValue {
-    Mine,
-    Number(u32),
+    Mine(i32),
+    Number(i32),
+}
+
+impl CellValue {
+    pub fn is_mine(self) -> bool {
+        matches!(self, Mine(_))
+    }
+
+    fn weight(self) -> i32 {
+        match self {
+            Mine(weight) => weight,
+            Number(_) => 0,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -33,27 +52,108 @@ impl Default for Cell {
     }
 }
 
-fn neighborhood_of(coord: Coord, size: Size) -> impl Iterator<Item=Coord> {
-    Coord::DIRECTIONS.iter()
-        .map(move |&d| coord + d)
-        .filter(move |&c| size.contains(c))
+impl Cell {
+    pub fn is_covered(&self) -> bool {
+        matches!(self.state, Covered | Flagged(_))
+    }
+}
+
+// How many mines a mined cell can hold
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Multiplicity {
+    // exactly one, the classic game
+    Single,
+    // one to three
+    Multi,
+    // one to three, or a negative mine
+    Negative,
+}
+
+impl Multiplicity {
+    // negative mines come last so that flags cycle through the usual weights first
+    fn weights(self) -> &'static [i32] {
+        match self {
+            Multiplicity::Single => &[1],
+            Multiplicity::Multi => &[1, 2, 3],
+            Multiplicity::Negative => &[1, 2, 3, -1],
+        }
+    }
+
+    // the weight of a flag after clicking it again, None takes it away
+    fn next_flag(self, weight: i32) -> Option<i32> {
+        let weights = self.weights();
+        let position = weights.iter().position(|&w| w == weight)?;
+        weights.get(position + 1).copied()
+    }
+}
+
+impl FromStr for Multiplicity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "single" => Ok(Self::Single),
+            "multi" => Ok(Self::Multi),
+            "negative" => Ok(Self::Negative),
+            _ => Err(()),
+        }
+    }
+}
+
+// How mines are laid out when the first cell is clicked
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Generation {
+    // anywhere but the clicked cell
+    Random,
+    // the clicked cell is always a zero, so the first click opens up an area
+    Opening,
+    // an opening from which the whole board can be cleared by logic alone
+    NoGuess,
+}
+
+impl FromStr for Generation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(Self::Random),
+            "opening" => Ok(Self::Opening),
+            "noguess" => Ok(Self::NoGuess),
+            _ => Err(()),
+        }
+    }
 }
 
+// Giving up on no-guess generation after this many layouts, or once checking them has taken this
+// many steps of the solver's search; dense boards may have none at all
+const NO_GUESS_ATTEMPTS: u32 = 1000;
+const NO_GUESS_BUDGET: u32 = 50 * solver::SEARCH_BUDGET;
+
 // "state" (win/loss) is not part of the MineField struct because we may support other modes of
 // deciding game outcome, such as Multiple Lives or Tap in Windows 10 Minesweeper daily challenges.
-// Instead we provide an interface to access the current stats across the mine field.
+// Instead we provide an interface to access the current stats across the mine field, on which the
+// objectives in objective.rs are built.
+#[derive(Clone)]
 pub struct MineFieldStats {
     pub uncovered_blank: u32,
     pub covered_mine: u32,
     pub exploded: u32,
+    pub flagged: u32,
 }
 
+#[derive(Clone)]
 pub struct Field {
     pub initialized: bool,
     pub size: Size,
+    pub topology: Topology,
+    pub multiplicity: Multiplicity,
+    // the number of mined cells, whatever their weight
     pub mines: u32,
     pub stats: MineFieldStats,
     data: Box<[Cell]>,
+    // a cell to click for each opening and for each number not bordering one, so that the 3BV of
+    // the board (the least clicks needed to clear it) is how many there are
+    bbbv: Vec<Coord>,
 }
 
 impl Index<Coord> for Field {
@@ -72,19 +172,23 @@ impl IndexMut<Coord> for Field {
 }
 
 impl Field {
-    pub fn new(rows: u32, columns: u32, mines: u32) -> Self {
-        let rows = rows.max(2);
+    pub fn new(rows: u32, columns: u32, mines: u32, topology: Topology, multiplicity: Multiplicity) -> Self {
+        let rows = topology.fit_rows(rows.max(2));
         let columns = columns.max(2);
         let mines = mines.clamp(1, rows * columns - 1);
         Self {
             initialized: false,
             data: vec![Cell::default(); (columns * rows) as _].into(),
+            bbbv: Vec::new(),
             size: Size(rows, columns),
+            topology,
+            multiplicity,
             mines,
             stats: MineFieldStats {
                 uncovered_blank: 0,
                 covered_mine: mines,
                 exploded: 0,
+                flagged: 0,
             },
         }
     }
@@ -93,93 +197,359 @@ impl Field {
         self.data.chunks(self.size.1 as _)
     }
 
+    pub fn neighborhood(&self, center: Coord) -> impl Iterator<Item=Coord> {
+        self.topology.neighborhood(center, self.size)
+    }
+
     fn iter_neighborhood(&self, center: Coord) -> impl Iterator<Item=&Cell> {
-        neighborhood_of(center, self.size).map(move |i| &self[i])
+        self.neighborhood(center).map(move |i| &self[i])
+    }
+
+    // A zero next to negative mines is not safe to open up from, so only zeros without any mines
+    // around are blank
+    pub fn is_blank(&self, coord: Coord) -> bool {
+        self[coord].value == Number(0) && !self.iter_neighborhood(coord).any(|c| c.value.is_mine())
+    }
+
+    // the sum of all mine weights; the weights are only drawn with the layout, so until then
+    // every mine counts once
+    pub fn mine_total(&self) -> i32 {
+        if self.initialized {
+            self.data.iter().map(|c| c.value.weight()).sum()
+        } else {
+            self.mines as i32
+        }
+    }
+
+    // the mines accounted for by flags and explosions
+    pub fn mines_marked(&self) -> i32 {
+        self.data.iter()
+            .map(|c| match c.state {
+                Flagged(weight) => weight,
+                Exploded => c.value.weight(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    // the layout depends only on the random number generator, so a seeded one gives a
+    // reproducible board.  Returns what the layout turned out to be: a no-guess board only if one
+    // was found, and an opening only if there was room for one.
+    pub fn initialize(&mut self, avoid: Coord, generation: Generation, rng: &mut impl rand::Rng) -> Generation {
+        let neighborhood: Vec<_> = iter::once(avoid)
+            .chain(self.neighborhood(avoid))
+            .collect();
+        // fall back to only avoiding the clicked cell if there is no room for an opening
+        let excluded = if generation == Generation::Random
+            || self.mines as usize + neighborhood.len() > self.size.size() as usize {
+            &neighborhood[..1]
+        } else {
+            &neighborhood[..]
+        };
+
+        let mut result = if excluded.len() > 1 { Generation::Opening } else { Generation::Random };
+        // counting steps rather than time keeps seeded boards the same on any machine
+        let mut budget = NO_GUESS_BUDGET;
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            self.place_mines(excluded, rng);
+            // the solver only knows about single mines, so other boards are never said to need no guessing
+            if generation != Generation::NoGuess || self.multiplicity != Multiplicity::Single {
+                break;
+            }
+            if solver::is_solvable(self, avoid, &mut budget) {
+                result = Generation::NoGuess;
+                break;
+            }
+            if budget == 0 {
+                break;
+            }
+        }
+        // if no attempt succeeded, the last layout is kept
+        self.initialized = true;
+        self.bbbv = self.find_bbbv();
+        result
+    }
+
+    // A field with the given mines (and their weights) already laid, and some cells uncovered
+    pub fn with_layout(size: Size, topology: Topology, mines: &[(Coord, i32)], uncovered: &[Coord]) -> Self {
+        let multiplicity = if mines.iter().any(|&(_, w)| w < 0) {
+            Multiplicity::Negative
+        } else if mines.iter().any(|&(_, w)| w > 1) {
+            Multiplicity::Multi
+        } else {
+            Multiplicity::Single
+        };
+        let mut field = Self::new(size.0, size.1, 1, topology, multiplicity);
+        field.mines = mines.len() as _;
+        field.stats.covered_mine = field.mines;
+        for &(coord, weight) in mines {
+            field[coord].value = Mine(weight);
+        }
+        field.count_mines();
+        field.initialized = true;
+        field.bbbv = field.find_bbbv();
+        field.reveal(uncovered.iter().copied());
+        field
+    }
+
+    // Moves the mines to the given cells, which must agree with every number uncovered so far; only
+    // single mines are moved
+    pub fn relayout(&mut self, mines: &[Coord]) {
+        for cell in self.data.iter_mut() {
+            cell.value = Number(0);
+        }
+        for &coord in mines {
+            self[coord].value = Mine(1);
+        }
+        self.count_mines();
+        self.bbbv = self.find_bbbv();
     }
 
-    pub fn initialize(&mut self, avoid: Coord) {
-        let avoid_index = self.size.index(avoid);
-        let mut rng = rand::thread_rng();
-        for mut i in rand::seq::index::sample(&mut rng, (self.size.size() - 1) as _, self.mines as _) {
-            if i >= avoid_index {
-                i += 1;
+    fn find_bbbv(&self) -> Vec<Coord> {
+        let mut reached = vec![false; self.size.size() as _];
+        let mut result = Vec::new();
+        for coord in self.size.valid_indices() {
+            if self.is_blank(coord) && !reached[self.size.index(coord)] {
+                result.push(coord);
+                // everything this opening uncovers at once
+                let mut stack = vec![coord];
+                while let Some(c) = stack.pop() {
+                    if !reached[self.size.index(c)] {
+                        reached[self.size.index(c)] = true;
+                        if self.is_blank(c) {
+                            stack.extend(self.neighborhood(c));
+                        }
+                    }
+                }
             }
-            self.data[i].value = Mine;
         }
+        result.extend(self.size.valid_indices()
+            .filter(|&c| !reached[self.size.index(c)] && !self[c].value.is_mine()));
+        result
+    }
+
+    // how much of the board's 3BV has been cleared, and the 3BV itself
+    pub fn bbbv(&self) -> (u32, u32) {
+        let done = self.bbbv.iter().filter(|&&c| self[c].state == Uncovered).count();
+        (done as _, self.bbbv.len() as _)
+    }
+
+    fn place_mines(&mut self, excluded: &[Coord], rng: &mut impl rand::Rng) {
+        let candidates: Vec<_> = self.size.valid_indices()
+            .filter(|c| !excluded.contains(c))
+            .collect();
+        for cell in self.data.iter_mut() {
+            cell.value = Number(0);
+        }
+        let weights = self.multiplicity.weights();
+        for i in rand::seq::index::sample(rng, candidates.len(), self.mines as _) {
+            self[candidates[i]].value = Mine(weights[rng.gen_range(0..weights.len())]);
+        }
+        self.count_mines();
+    }
+
+    fn count_mines(&mut self) {
         for coord in self.size.valid_indices() {
-            if self[coord].value != Mine {
+            if !self[coord].value.is_mine() {
                 let value = self.iter_neighborhood(coord)
-                    .filter(|c| c.value == Mine)
-                    .count() as u32;
+                    .map(|c| c.value.weight())
+                    .sum();
                 self[coord].value = Number(value);
             }
         }
-        self.initialized = true;
     }
 
     // primitive actions
     fn reveal(&mut self, coords: impl Iterator<Item=Coord>) {
         // flood-fill
-        // reveal all adjacent cells if the current cell has a value of 0
+        // reveal all adjacent cells if the current cell is blank
         let mut queue = VecDeque::with_capacity(self.size.size() as _);
         queue.extend(coords);
         while let Some(coord) = queue.pop_front() {
             if self[coord].state == Covered {
                 self[coord].state = Uncovered;
-                if self[coord].value == Mine {
+                if self[coord].value.is_mine() {
                     self.stats.covered_mine -= 1;
                 } else {
                     self.stats.uncovered_blank += 1;
                 }
-                if self[coord].value == Number(0) {
-                    queue.extend(neighborhood_of(coord, self.size)
+                if self.is_blank(coord) {
+                    queue.extend(self.neighborhood(coord)
                         .filter(|&i| self[i].state == Covered));
                 }
             }
         }
     }
 
-    fn reveal_around(&mut self, coord: Coord) {
-        self.reveal(neighborhood_of(coord, self.size));
-    }
-
     // simple actions
     pub fn uncover(&mut self, coord: Coord) {
-        if self[coord].value == Mine {
+        if self[coord].value.is_mine() {
             self.stats.exploded += 1;
+            self.stats.covered_mine -= 1;
             self[coord].state = Exploded;
         } else {
             self.reveal(iter::once(coord));
         }
     }
 
-    // uncovers around cell, returns true if the field has changed
-    pub fn uncover_around(&mut self, coord: Coord) -> bool {
+    // puts a flag on a covered cell, raises its weight or takes it away, returns true if the field
+    // has changed
+    pub fn toggle_flag(&mut self, coord: Coord) -> bool {
+        match self[coord].state {
+            Covered => {
+                self[coord].state = Flagged(1);
+                self.stats.flagged += 1;
+                true
+            }
+            Flagged(weight) => {
+                if let Some(weight) = self.multiplicity.next_flag(weight) {
+                    self[coord].state = Flagged(weight);
+                } else {
+                    self[coord].state = Covered;
+                    self.stats.flagged -= 1;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // the cells "chording" on a number uncovers: its covered neighbors, once the flags (or exploded
+    // mines) around it add up to it
+    pub fn chord_targets(&self, coord: Coord) -> Vec<Coord> {
         match self[coord].value {
-            Mine => false,
+            Mine(_) => Vec::new(),
             Number(value) => {
-                // count the number of adjacent covered cells and adjacent uncovered mine cells
                 // there are certainly iterator chains that can do this in one statement but
                 // a loop seems more readable
-                let mut covered = 0;
-                let mut uncovered_mines = 0;
-                for c in self.iter_neighborhood(coord) {
-                    if c.state == Uncovered {
-                        if c.value == Mine { uncovered_mines += 1; }
-                    } else {
-                        covered += 1;
+                let mut covered = Vec::new();
+                let mut marked = 0;
+                for c in self.neighborhood(coord) {
+                    match self[c].state {
+                        Covered => covered.push(c),
+                        Flagged(weight) => marked += weight,
+                        Exploded | Uncovered => marked += self[c].value.weight(),
                     }
                 }
-                if covered == 0 {
-                    false
-                } else if uncovered_mines == value || covered + uncovered_mines == value {
-                    // reveal all adjacent cells
-                    self.reveal_around(coord);
-                    true
-                } else {
-                    false
-                }
+                if marked == value { covered } else { Vec::new() }
+            }
+        }
+    }
+
+    // chords on a number, returns true if the field has changed
+    pub fn uncover_around(&mut self, coord: Coord) -> bool {
+        let targets = self.chord_targets(coord);
+        // a wrong flag means one of these is a mine
+        for &c in &targets {
+            if self[c].state == Covered {
+                self.uncover(c);
             }
         }
+        !targets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn generate(rows: u32, columns: u32, mines: u32, start: Coord, seed: u64) -> (Field, Generation) {
+        let mut field = Field::new(rows, columns, mines, Topology::default(), Multiplicity::Single);
+        let generation = field.initialize(start, Generation::NoGuess, &mut ChaCha8Rng::seed_from_u64(seed));
+        (field, generation)
+    }
+
+    fn mines(field: &Field) -> Vec<Coord> {
+        field.size.valid_indices().filter(|&c| field[c].value.is_mine()).collect()
+    }
+
+    #[test]
+    fn seeds_give_the_same_board_every_time() {
+        let (field, _) = generate(8, 8, 10, Coord(4, 4), 1);
+        assert_eq!(mines(&field), mines(&generate(8, 8, 10, Coord(4, 4), 1).0));
+        assert_ne!(mines(&field), mines(&generate(8, 8, 10, Coord(4, 4), 2).0));
+        // nor between versions, or shared seeds and daily challenges would change
+        assert_eq!(mines(&field), vec![
+            Coord(1, 2), Coord(1, 3), Coord(1, 7), Coord(2, 3), Coord(3, 1),
+            Coord(4, 0), Coord(6, 0), Coord(6, 4), Coord(6, 7), Coord(7, 2),
+        ]);
+    }
+
+    #[test]
+    fn finds_boards_without_guessing() {
+        let start = Coord(2, 2);
+        for seed in 0..10 {
+            let (field, generation) = generate(5, 5, 10, start, seed);
+            assert!(generation == Generation::NoGuess);
+            assert!(solver::is_solvable(&field, start, &mut solver::SEARCH_BUDGET.clone()));
+        }
+    }
+
+    #[test]
+    fn falls_back_to_an_opening_when_every_board_needs_a_guess() {
+        // the mine ends up in one of the last two columns, where a 50/50 is left whichever it is
+        let (field, generation) = generate(2, 4, 1, Coord(1, 0), 0);
+        assert!(generation == Generation::Opening);
+        assert!(field.initialized && field.is_blank(Coord(1, 0)));
+    }
+
+    #[test]
+    fn falls_back_to_random_without_room_for_an_opening() {
+        let (_, generation) = generate(4, 4, 10, Coord(1, 1), 0);
+        assert!(generation == Generation::Random);
+    }
+
+    fn layout(size: Size, mines: &[(Coord, i32)], uncovered: &[Coord]) -> Field {
+        Field::with_layout(size, Topology::default(), mines, uncovered)
+    }
+
+    // a 1 in the middle of a 3x3 board, with the mine in a corner
+    fn one_in_the_middle() -> Field {
+        layout(Size(3, 3), &[(Coord(0, 0), 1)], &[Coord(1, 1)])
+    }
+
+    #[test]
+    fn chords_once_the_flags_add_up() {
+        let mut field = one_in_the_middle();
+        assert!(field.chord_targets(Coord(1, 1)).is_empty());
+        assert!(!field.uncover_around(Coord(1, 1)));
+        field.toggle_flag(Coord(0, 0));
+        assert_eq!(field.chord_targets(Coord(1, 1)).len(), 7);
+        assert!(field.uncover_around(Coord(1, 1)));
+        assert_eq!(field.stats.uncovered_blank, 8);
+        assert_eq!(field.stats.exploded, 0);
+    }
+
+    #[test]
+    fn chording_next_to_a_wrong_flag_explodes() {
+        let mut field = one_in_the_middle();
+        field.toggle_flag(Coord(2, 2));
+        assert!(field.uncover_around(Coord(1, 1)));
+        assert!(field[Coord(0, 0)].state == State::Exploded);
+        assert_eq!(field.stats.exploded, 1);
+    }
+
+    #[test]
+    fn counts_openings_and_the_cells_they_miss_for_3bv() {
+        // an opening on either side of the mine, which reach neither number above and below it
+        let field = layout(Size(3, 5), &[(Coord(1, 2), 1)], &[]);
+        assert_eq!(field.bbbv(), (0, 4));
+        // the zero between a mine and a negative mine doesn't open up, nor does the one below it
+        let field = layout(Size(3, 3), &[(Coord(0, 0), 1), (Coord(0, 2), -1)], &[]);
+        assert!(field.multiplicity == Multiplicity::Negative);
+        assert_eq!(field.bbbv(), (0, 2));
+        // uncovering the opening clears its part
+        let field = layout(Size(3, 5), &[(Coord(1, 2), 1)], &[Coord(1, 0)]);
+        assert_eq!(field.bbbv(), (1, 4));
+    }
+
+    #[test]
+    fn weighted_mines_are_never_said_to_need_no_guessing() {
+        let mut field = Field::new(8, 8, 10, Topology::default(), Multiplicity::Multi);
+        let generation = field.initialize(Coord(4, 4), Generation::NoGuess, &mut ChaCha8Rng::seed_from_u64(0));
+        assert!(generation == Generation::Opening);
     }
 }
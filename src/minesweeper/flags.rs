@@ -0,0 +1,151 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use telegram_bot::{InlineKeyboardMarkup, MessageEntity, MessageEntityKind, User, UserId};
+
+use crate::game::{self, Coord, InteractResult};
+use super::field::{Field, Generation, Multiplicity, State};
+use super::game::to_string;
+use super::topology::Topology;
+use super::viewport::Viewport;
+
+const ROWS: u32 = 16;
+const COLUMNS: u32 = 16;
+const MINES: u32 = 51;
+
+// Minesweeper Flags: two players take turns uncovering cells, and whoever finds a mine scores it
+// and goes again.  The first to find more than half of the mines wins.
+pub struct Game {
+    field: Field,
+    seed: u64,
+    // who found each mine, by cell index; false for the first player
+    found: Vec<Option<bool>>,
+    first_player: String,
+    second_player: (UserId, String),
+    // false while it is the first player's turn, like in othello
+    player: bool,
+    view: Viewport,
+}
+
+impl Game {
+    // "/flags @opponent", the opponent moves first
+    pub fn from_message<'a>(
+        data: &str, entities: impl IntoIterator<Item=&'a MessageEntity>, user: &User
+    ) -> Option<(Self, String, InlineKeyboardMarkup)> {
+        let first_player = entities.into_iter()
+            .find_map(|e| (e.kind == MessageEntityKind::Mention).then(|| {
+                let start = (e.offset + 1) as usize;
+                let end = (e.offset + e.length) as usize;
+                data[start..end].to_owned()
+            }))?;
+        let second_player = (
+            user.id,
+            user.username.to_owned().unwrap_or_else(|| user.first_name.to_owned())
+        );
+        let field = Field::new(ROWS, COLUMNS, MINES, Topology::default(), Multiplicity::Single);
+        let game = Game {
+            found: vec![None; field.size.size() as _],
+            view: Viewport::new(field.size, field.topology.kernel),
+            field,
+            seed: rand::random(),
+            first_player,
+            second_player,
+            player: false,
+        };
+        let text = game.get_text();
+        let inline_keyboard = game.to_inline_keyboard();
+        Some((game, text, inline_keyboard))
+    }
+
+    fn score(&self, player: bool) -> usize {
+        self.found.iter().filter(|&&f| f == Some(player)).count()
+    }
+
+    fn is_over(&self) -> bool {
+        let found = self.field.stats.exploded;
+        found == self.field.mines || [false, true].iter().any(|&p| self.score(p) * 2 > self.field.mines as usize)
+    }
+
+    fn get_text(&self) -> String {
+        let scores = (self.score(false), self.score(true));
+        let mut text = format!("🔴 {} {} vs {} {} 🔵\n{} mines left", self.first_player, scores.0,
+            scores.1, self.second_player.1, self.field.mines - self.field.stats.exploded);
+        if self.is_over() {
+            use std::cmp::Ordering::*;
+            match scores.0.cmp(&scores.1) {
+                Less => text += format!("\nWinner: {}", self.second_player.1).as_str(),
+                Equal => text += "\nDraw game.",
+                Greater => text += format!("\nWinner: {}", self.first_player).as_str(),
+            }
+            text += format!("\nSeed: {}", self.seed).as_str();
+        } else if self.player {
+            text += format!("\nTurn: 🔵 {}", self.second_player.1).as_str();
+        } else {
+            text += format!("\nTurn: 🔴 {}", self.first_player).as_str();
+        }
+        if let Some(view) = self.view.describe() {
+            text += "\n";
+            text += view.as_str();
+        }
+        text
+    }
+
+    fn to_inline_keyboard(&self) -> InlineKeyboardMarkup {
+        let over = self.is_over();
+        self.view.render(|coord| match self.found[self.field.size.index(coord)] {
+            Some(false) => "🔴".to_owned(),
+            Some(true) => "🔵".to_owned(),
+            // the mines nobody found are shown at the end
+            None if over && self.field[coord].value.is_mine() => "💣".to_owned(),
+            None => to_string(&self.field, coord),
+        }).into()
+    }
+
+    fn is_current_player(&self, user: &User) -> bool {
+        if !self.player {
+            user.username.contains(&self.first_player)
+        } else {
+            user.id == self.second_player.0
+        }
+    }
+
+    // returns true if a cell was uncovered
+    fn play(&mut self, coord: Coord) -> bool {
+        if self.is_over() || self.field[coord].state != State::Covered {
+            return false;
+        }
+        if !self.field.initialized {
+            let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+            self.field.initialize(coord, Generation::Random, &mut rng);
+        }
+        self.field.uncover(coord);
+        if self.field[coord].state == State::Exploded {
+            // finding a mine earns another turn
+            self.found[self.field.size.index(coord)] = Some(self.player);
+        } else {
+            self.player = !self.player;
+        }
+        true
+    }
+}
+
+impl game::Game for Game {
+    fn interact(&mut self, coord: Coord, user: &User) -> Option<InteractResult> {
+        (self.is_current_player(user) && self.play(coord)).then(|| InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.to_inline_keyboard()),
+            game_end: self.is_over(),
+            new_game: None,
+            reply: None,
+        })
+    }
+
+    fn press(&mut self, button: &str, _user: &User) -> Option<InteractResult> {
+        self.view.pan(button).then(|| InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.to_inline_keyboard()),
+            game_end: false,
+            new_game: None,
+            reply: None,
+        })
+    }
+}
@@ -1,12 +1,69 @@
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use telegram_bot::{InlineKeyboardButton, InlineKeyboardMarkup};
+use thiserror::Error;
 
-use crate::game::Coord;
-use crate::grid_game::{GameState, GridGame};
-use crate::grid_game::GameState::{GameOver, Normal, Solved};
-use super::field::{Cell, Field, State, CellValue};
+use crate::game::{self, Coord};
+use crate::grid_game::{GameState, GridGame, HINT};
+use crate::grid_game::GameState::Normal;
+use super::code::Layout;
+use super::field::{Field, Generation, Multiplicity, State, CellValue};
+use super::objective::{Clear, ClearCells, Objective, Penalty, Tap, Treasure};
+use super::replay::{Action, Move, Replay, CREDITS, REPLAY};
+use super::solver;
+use super::topology::{Edges, Topology};
+use super::viewport::{MAX_ROWS, MAX_SIZE, max_columns, Viewport};
 
+// callback data of the button switching between digging and flagging
+pub const TOGGLE_FLAG: &str = "flag";
+// the name the bot's own moves are recorded under
+const AUTOPLAYER: &str = "🤖";
+// how many times the mines are laid again for an objective that can't be met on the first layout
+const LAYOUT_ATTEMPTS: u32 = 20;
+// ticks between the bot's moves, as a message in a group may only be edited about 20 times a minute
+const AUTOPLAY_TICKS: u32 = 3;
+
+// Options that were recognized but can't be used, told to whoever started the game
+#[derive(Error, Debug)]
+pub enum OptionError {
+    #[error("The seed has to be a whole number, not \"{0}\".")]
+    Seed(String),
+    #[error("{0} has to be a whole number, not \"{1}\".")]
+    Number(&'static str, String),
+    #[error("At least one mine has to be tapped.")]
+    NoTaps,
+    #[error("At least one cell has to be cleared.")]
+    NoCells,
+    #[error("At least one move has to be allowed.")]
+    NoMoves,
+    #[error("A speedrun has to last at least a second.")]
+    NoTime,
+    #[error("Fair mines can't go with tap= or treasure.")]
+    FairObjective,
+    #[error("Only one of lives=, penalty=, clear=, tap= and treasure can be chosen.")]
+    Objectives,
+    #[error("moves= only goes with tap=.")]
+    Moves,
+    #[error("There is nowhere left to hide the treasure on this board.")]
+    NoTreasure,
+    #[error("No-guess boards can only have single mines.")]
+    NoGuessWeights,
+    #[error("\"{0}\" is not a valid board code.")]
+    Code(String),
+    #[error("The board below the command can't be played: it needs rows of the same length, of '*' for a mine, '.' for a covered cell and 'o' for an uncovered one, and something left to clear.")]
+    Grid,
+    #[error("Boards can be at most {0} x {0}.")]
+    TooLarge(u32),
+}
+
+fn parse_number<T: FromStr>(option: &'static str, value: &str) -> Result<T, OptionError> {
+    value.parse().map_err(|_| OptionError::Number(option, value.to_owned()))
+}
+
+// Classic has flags and chording, NoFlag has neither
 #[derive(Eq, PartialEq)]
 pub enum Mode {
     Classic,
@@ -16,6 +73,41 @@ pub enum Mode {
 pub struct Game {
     field: Field,
     mode: Mode,
+    // whether clicking a covered cell flags it instead of uncovering it
+    flagging: bool,
+    generation: Generation,
+    seed: u64,
+    objective: Box<dyn Objective>,
+    moves: u32,
+    // whether hints may be asked for, and the suggested cell with its chance of being a mine
+    hints: bool,
+    hint: Option<(Coord, f64)>,
+    // every click on the board, including those that changed nothing, unlike moves
+    clicks: u32,
+    // timing starts with the first click and stops when the game ends
+    started: Option<Instant>,
+    finished: Option<Duration>,
+    // the countdown of a speedrun, the game is lost once it runs out
+    time_limit: Option<Duration>,
+    timed_out: bool,
+    // the seconds left when the countdown was last shown by a tick
+    countdown: Option<u64>,
+    // the cells uncovered before the player took over, or the first click, for exporting the board
+    opening: Vec<Coord>,
+    // the board as it was when the mines were laid, and every click since
+    start: Option<Field>,
+    record: Vec<Move>,
+    view: Viewport,
+    // what went wrong with the click that lost the game
+    post_mortem: Option<String>,
+    // whether the bot plays by itself, a move every few ticks, and why it made the last one
+    auto: bool,
+    reasoning: Option<String>,
+    // ticks since the bot's last move
+    waited: u32,
+    // Kaboom rules: the mines move so that a guess is safe when it is forced and a mine when a safe
+    // cell could have been found instead
+    fair: bool,
 }
 
 impl FromStr for Mode {
@@ -31,85 +123,619 @@ impl FromStr for Mode {
 }
 
 impl Game {
-    pub fn from_message(data: &str) -> Self {
+    // Options go on the first line, a hand-made board may follow on the next ones
+    pub fn from_message(data: &str) -> Result<Self, OptionError> {
         // constraints:
-        // 2 <= rows <= 10
-        // 2 <= columns <= 8
+        // 2 <= rows <= 30, 10 by default
+        // 2 <= columns <= 30, by default 8, or 7 on hex grids as every row takes a button of padding
         // 1 <= mines < rows * columns
+        // boards larger than the defaults are shown through a viewport
+        let (options, grid) = data.split_once('\n').unwrap_or((data, ""));
         let mut args = Vec::new();
         let mut mode = Mode::Classic;
+        let mut generation = None;
+        let mut topology = Topology::default();
+        let mut multiplicity = Multiplicity::Single;
+        let mut seed = rand::random();
+        let mut lives = None;
+        let mut penalty = None;
+        let mut clear = None;
+        let mut tap = None;
+        let mut moves = None;
+        let mut treasure = false;
+        let mut auto = false;
+        let mut fair = false;
+        let mut time_limit = None;
+        let mut code = None;
 
-        for arg in data.split_whitespace().skip(1) {
-            if let Ok(game_mode) = arg.parse() {
+        for arg in options.split_whitespace().skip(1) {
+            if let Some(value) = arg.strip_prefix("seed=") {
+                seed = value.parse().map_err(|_| OptionError::Seed(value.to_owned()))?;
+            } else if let Some(value) = arg.strip_prefix("lives=") {
+                lives = Some(parse_number::<u32>("lives=", value)?.max(1));
+            } else if let Some(value) = arg.strip_prefix("penalty=") {
+                penalty = Some(parse_number("penalty=", value)?);
+            } else if let Some(value) = arg.strip_prefix("clear=") {
+                let cells = parse_number("clear=", value)?;
+                if cells == 0 {
+                    return Err(OptionError::NoCells);
+                }
+                clear = Some(cells);
+            } else if let Some(value) = arg.strip_prefix("tap=") {
+                let mines = parse_number("tap=", value)?;
+                if mines == 0 {
+                    return Err(OptionError::NoTaps);
+                }
+                tap = Some(mines);
+            } else if let Some(value) = arg.strip_prefix("moves=") {
+                let limit = parse_number("moves=", value)?;
+                if limit == 0 {
+                    return Err(OptionError::NoMoves);
+                }
+                moves = Some(limit);
+            } else if let Some(value) = arg.strip_prefix("code=") {
+                code = Some(Layout::decode(value).ok_or_else(|| OptionError::Code(value.to_owned()))?);
+            } else if let Some(value) = arg.strip_prefix("speedrun=") {
+                let seconds = parse_number("speedrun=", value)?;
+                if seconds == 0 {
+                    return Err(OptionError::NoTime);
+                }
+                time_limit = Some(Duration::from_secs(seconds));
+            } else if arg.eq_ignore_ascii_case("treasure") {
+                treasure = true;
+            } else if arg.eq_ignore_ascii_case("auto") {
+                auto = true;
+            } else if arg.eq_ignore_ascii_case("fair") {
+                fair = true;
+            } else if let Ok(game_mode) = arg.parse() {
                 mode = game_mode;
+            } else if let Ok(policy) = arg.parse() {
+                generation = Some(policy);
+            } else if let Ok(kernel) = arg.parse() {
+                topology.kernel = kernel;
+            } else if let Ok(edges) = arg.parse::<Edges>() {
+                topology.edges = edges;
+            } else if let Ok(weights) = arg.parse::<Multiplicity>() {
+                multiplicity = weights;
             } else if let Ok(num) = arg.parse() {
-                args.push(num);
-                if args.len() >= 3 { break; }
+                // options may still follow the numbers
+                if args.len() < 3 { args.push(num); }
             }
         }
 
-        let rows = args.get(0).copied().unwrap_or(10).min(10);
-        let columns = args.get(1).copied().unwrap_or(8).min(8);
+        let layout = match code {
+            Some(layout) => Some(layout),
+            None if grid.trim().is_empty() => None,
+            None => Some(Layout::parse_grid(grid, topology).ok_or(OptionError::Grid)?),
+        };
+        if layout.as_ref().filter(|layout| layout.size.0 > MAX_SIZE || layout.size.1 > MAX_SIZE).is_some() {
+            return Err(OptionError::TooLarge(MAX_SIZE));
+        }
+        let rows = args.first().copied().unwrap_or(MAX_ROWS).min(MAX_SIZE);
+        let columns = args.get(1).copied().unwrap_or_else(|| max_columns(topology.kernel)).min(MAX_SIZE);
         let mines = args.get(2).copied().unwrap_or_else(|| rows * columns / 10);
-        Self {
-            field: Field::new(rows, columns, mines),
+        let objectives = [lives.is_some(), penalty.is_some(), clear.is_some(), tap.is_some(), treasure];
+        if objectives.iter().filter(|&&chosen| chosen).count() > 1 {
+            return Err(OptionError::Objectives);
+        }
+        if moves.is_some() && tap.is_none() {
+            return Err(OptionError::Moves);
+        }
+        // moving the mines could bury the treasure, and would keep them away from taps
+        if fair && (treasure || tap.is_some()) {
+            return Err(OptionError::FairObjective);
+        }
+        let mut objective: Box<dyn Objective> = if treasure {
+            Box::new(Treasure::default())
+        } else if let Some(mines) = tap {
+            Box::new(Tap { mines, moves: moves.unwrap_or(mines * 3) })
+        } else if let Some(cells) = clear {
+            Box::new(ClearCells { cells })
+        } else if let Some(seconds) = penalty {
+            Box::new(Penalty { seconds })
+        } else {
+            Box::new(Clear { lives: lives.unwrap_or(1) })
+        };
+        let laid_out = layout.is_some();
+        let (field, opening) = match layout {
+            Some(layout) => {
+                let field = layout.to_field();
+                let start = layout.uncovered.first().copied().unwrap_or(Coord(0, 0));
+                if !objective.initialize(&field, start, &mut ChaCha8Rng::seed_from_u64(seed)) {
+                    return Err(OptionError::NoTreasure);
+                }
+                (field, layout.uncovered)
+            }
+            None => {
+                let field = Field::new(rows, columns, mines, topology, multiplicity);
+                // besides the first click, at least one more cell has to be free of mines
+                if treasure && field.mines + 2 > field.size.size() {
+                    return Err(OptionError::NoTreasure);
+                }
+                (field, Vec::new())
+            }
+        };
+        // the solver can't tell whether a board of weighted mines needs guessing
+        if generation == Some(Generation::NoGuess) && field.multiplicity != Multiplicity::Single {
+            return Err(OptionError::NoGuessWeights);
+        }
+        // a board laid out by hand is played as it is, whatever was asked for
+        let generation = if laid_out {
+            Generation::Random
+        } else {
+            // the bot starts with an opening unless told otherwise, a demo lost on its second click
+            // shows little
+            generation.unwrap_or(if auto { Generation::Opening } else { Generation::Random })
+        };
+        let game = Self::new(field, seed);
+        Ok(Self {
             mode,
+            generation,
+            objective,
+            time_limit,
+            opening,
+            // nobody is asking for hints while the bot plays
+            hints: game.hints && !auto,
+            auto,
+            // the solver only moves single mines
+            fair: fair && game.field.multiplicity == Multiplicity::Single,
+            ..game
+        })
+    }
+
+    // A classic game on the given field
+    fn new(field: Field, seed: u64) -> Self {
+        Self {
+            mode: Mode::Classic,
+            flagging: false,
+            generation: Generation::Random,
+            seed,
+            objective: Box::new(Clear { lives: 1 }),
+            moves: 0,
+            // the solver only handles single mines
+            hints: field.multiplicity == Multiplicity::Single,
+            hint: None,
+            clicks: 0,
+            started: None,
+            finished: None,
+            time_limit: None,
+            timed_out: false,
+            countdown: None,
+            opening: Vec::new(),
+            start: field.initialized.then(|| field.clone()),
+            record: Vec::new(),
+            view: Viewport::new(field.size, field.topology.kernel),
+            post_mortem: None,
+            auto: false,
+            reasoning: None,
+            waited: 0,
+            fair: false,
+            field,
+        }
+    }
+
+    // A classic game on a board laid out by hand
+    pub fn with_layout(layout: Layout) -> Self {
+        Self {
+            opening: layout.uncovered.clone(),
+            ..Self::new(layout.to_field(), rand::random())
+        }
+    }
+
+    // A no-guess board that comes with an opening already uncovered, so that the layout doesn't
+    // depend on where it is first clicked and everyone playing the same seed sees the same board.
+    // Hints are disabled as these boards are meant for competition.
+    pub fn with_opening(rows: u32, columns: u32, mines: u32, seed: u64) -> Self {
+        let field = Field::new(rows, columns, mines, Topology::default(), Multiplicity::Single);
+        let mut game = Self {
+            generation: Generation::NoGuess,
+            hints: false,
+            ..Self::new(field, seed)
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let size = game.field.size;
+        let start = Coord(rng.gen_range(0..size.0 as _), rng.gen_range(0..size.1 as _));
+        game.generation = game.field.initialize(start, game.generation, &mut rng);
+        game.field.uncover(start);
+        game.opening.push(start);
+        game.start = Some(game.field.clone());
+        game
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.finished
+            .or_else(|| self.started.map(|started| started.elapsed()))
+            .unwrap_or_default()
+    }
+
+    // Looks at the board as it was before the click that blew up, using only what the players could
+    // see, to tell a forced guess from a mistake
+    fn analyze_loss(&self) -> Option<String> {
+        let (last, moves) = self.record.split_last()?;
+        let mut field = self.start.clone()?;
+        for m in moves {
+            m.action.apply(&mut field, m.coord);
+        }
+        let analysis = solver::analyze(&field)?;
+        // a chord may blow up any of the cells around it, the most likely one to be a mine is shown
+        let (coord, p) = field.size.valid_indices()
+            .filter(|&c| field[c].state == State::Covered && self.field[c].state == State::Exploded)
+            .filter_map(|c| analysis.probability(c).map(|p| (c, p)))
+            .fold(None, |worst, (c, p)| match worst {
+                Some((_, q)) if q >= p => worst,
+                _ => Some((c, p)),
+            })?;
+        let mut text = if last.action == Action::Chord {
+            format!("☠ Chording at {}, {} uncovered the fatal cell at {}, {}, which ",
+                last.coord.0, last.coord.1, coord.0, coord.1)
+        } else {
+            format!("☠ The fatal cell at {}, {} ", coord.0, coord.1)
+        };
+        if analysis.mines.contains(&coord) {
+            text += "was a certain mine, a mistake that could have been deduced";
+        } else if !analysis.safe.is_empty() {
+            text += format!("had a {:.0}% chance of being a mine, a mistake as {} safe cells could be deduced",
+                p * 100.0, analysis.safe.len()).as_str();
+        } else {
+            text += format!("had a {:.0}% chance of being a mine, a forced guess", p * 100.0).as_str();
+            if let Some((_, best)) = analysis.safest() {
+                text += format!(" (the best odds were {:.0}%)", best * 100.0).as_str();
+            }
+        }
+        Some(text)
+    }
+
+    // a click on the board, by a player or the bot, returns true if the field has changed
+    fn play(&mut self, coord: Coord, action: Action, user: &str) -> bool {
+        self.started.get_or_insert_with(Instant::now);
+        self.clicks += 1;
+        if action == Action::Dig && !self.field.initialized {
+            let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+            let requested = self.generation;
+            for _ in 0..LAYOUT_ATTEMPTS {
+                // what the board is said to be follows what could be laid out
+                self.generation = self.field.initialize(coord, requested, &mut rng);
+                // a treasure needs a cell the first click doesn't reach; should every layout be
+                // cleared by it, the game is simply won
+                if self.objective.initialize(&self.field, coord, &mut rng) {
+                    break;
+                }
+            }
+            self.opening.push(coord);
+            self.start = Some(self.field.clone());
+        }
+        let changed = if self.fair && action == Action::Chord {
+            // every cell a chord reaches is settled before it is uncovered, in turn
+            let targets = self.field.chord_targets(coord);
+            for &c in &targets {
+                if self.field[c].state == State::Covered {
+                    self.settle(c);
+                    self.field.uncover(c);
+                }
+            }
+            !targets.is_empty()
+        } else {
+            if self.fair && action == Action::Dig && self.field[coord].state == State::Covered {
+                self.settle(coord);
+            }
+            action.apply(&mut self.field, coord)
+        };
+        self.record.push(Move { coord, action, user: user.to_owned(), time: self.elapsed() });
+        if changed {
+            self.moves += 1;
+            self.hint = None;
+            if self.get_state() != Normal {
+                self.finished = Some(self.elapsed());
+            }
+            if self.get_state() == GameState::GameOver {
+                self.post_mortem = self.analyze_loss();
+            }
+        }
+        changed
+    }
+
+    // Decides whether a cell about to be dug is a mine, unless what is visible already does.  The
+    // board it started from is laid out the same way, which agrees with every move made so far, so
+    // that replays show the game as it was played.
+    fn settle(&mut self, coord: Coord) {
+        let analysis = match solver::analyze(&self.field) {
+            Some(analysis) => analysis,
+            None => return,
+        };
+        if analysis.safe.contains(&coord) || analysis.mines.contains(&coord) {
+            return;
+        }
+        let mine = !analysis.safe.is_empty();
+        if self.field[coord].value.is_mine() == mine {
+            return;
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed.wrapping_add(self.clicks as u64));
+        if let Some(mines) = solver::rearrange(&self.field, coord, mine, &mut rng) {
+            self.field.relayout(&mines);
+            if let Some(start) = &mut self.start {
+                start.relayout(&mines);
+            }
+        }
+    }
+
+    // moves once every AUTOPLAY_TICKS ticks, returns true if the field has changed
+    fn autoplay_tick(&mut self) -> bool {
+        self.waited += 1;
+        if self.waited < AUTOPLAY_TICKS {
+            return false;
+        }
+        self.waited = 0;
+        self.autoplay()
+    }
+
+    // The bot's move: what can be deduced first, marking mines before uncovering safe cells, then
+    // the cell least likely to be a mine.  Returns true if the field has changed.
+    fn autoplay(&mut self) -> bool {
+        let (coord, action, reasoning) = if !self.field.initialized {
+            let center = Coord((self.field.size.0 / 2) as _, (self.field.size.1 / 2) as _);
+            (center, Action::Dig, "Starting in the middle".to_owned())
+        } else {
+            let field = &self.field;
+            let covered = |c: &&Coord| field[**c].state == State::Covered;
+            let analysis = solver::analyze(field);
+            let mine = analysis.as_ref()
+                .and_then(|a| a.mines.iter().find(covered))
+                .filter(|_| self.mode == Mode::Classic);
+            let safe = analysis.as_ref().and_then(|a| a.safe.iter().find(covered));
+            if let Some(&c) = mine {
+                (c, Action::Flag, format!("{}, {} must be a mine", c.0, c.1))
+            } else if let Some(&c) = safe {
+                (c, Action::Dig, format!("{}, {} must be safe", c.0, c.1))
+            } else if let Some((c, p)) = analysis.as_ref().and_then(|a| a.safest()) {
+                (c, Action::Dig, format!("Stuck, guessing {}, {} with a {:.0}% chance of a mine", c.0, c.1, p * 100.0))
+            } else if let Some(c) = field.size.valid_indices().find(|&c| field[c].state == State::Covered) {
+                (c, Action::Dig, format!("Guessing {}, {}", c.0, c.1))
+            } else {
+                return false;
+            }
+        };
+        self.view.show(coord);
+        self.reasoning = Some(reasoning);
+        self.play(coord, action, AUTOPLAYER)
+    }
+
+    fn get_stats(&self) -> String {
+        let penalty = self.objective.penalty(&self.field);
+        let seconds = (self.elapsed() + penalty).as_secs_f64();
+        let (done, bbbv) = self.field.bbbv();
+        let mut text = format!("⏱ {:.1}s", seconds);
+        if penalty > Duration::default() {
+            text += format!(" with {}s penalty", penalty.as_secs()).as_str();
+        }
+        text += format!(", 3BV {} / {}", done, bbbv).as_str();
+        if seconds > 0.0 {
+            text += format!(", {:.2} 3BV/s", done as f64 / seconds).as_str();
         }
+        text += format!("\nClicks: {} useful, {} wasted", self.moves, self.clicks - self.moves).as_str();
+        if self.clicks > 0 {
+            text += format!(", efficiency {:.0}%", done as f64 * 100.0 / self.clicks as f64).as_str();
+        }
+        text
     }
 }
 
 impl GridGame for Game {
     fn get_state(&self) -> GameState {
-        let stats = &self.field.stats;
-        if stats.exploded > 0 {
-            GameOver
-        } else if stats.uncovered_blank + self.field.mines == self.field.size.size() {
-            Solved
+        if self.timed_out {
+            GameState::GameOver
         } else {
-            Normal
+            self.objective.get_state(&self.field, self.moves)
         }
     }
 
     fn get_text(&self) -> String {
-        format!("{} x {}\n{} left / {} mines", self.field.size.0, self.field.size.1,
-            self.field.stats.covered_mine, self.field.mines)
+        let mut rules = self.field.topology.describe();
+        match self.field.multiplicity {
+            Multiplicity::Single => (),
+            Multiplicity::Multi => rules.push("up to 3 mines per cell"),
+            Multiplicity::Negative => rules.push("up to 3 mines per cell, negative mines"),
+        }
+        match self.generation {
+            Generation::Random => (),
+            Generation::Opening => rules.push("opening start"),
+            Generation::NoGuess => rules.push("no guessing"),
+        }
+        if self.fair {
+            rules.push("fair mines");
+        }
+        let mut text = format!("{} x {}", self.field.size.0, self.field.size.1);
+        for rule in rules {
+            text += ", ";
+            text += rule;
+        }
+        if let Some(limit) = self.time_limit {
+            text += format!(", {}s speedrun", limit.as_secs()).as_str();
+        }
+        text += "\n";
+        text += self.objective.get_text(&self.field, self.moves).as_str();
+        if let Some(view) = self.view.describe() {
+            text += "\n";
+            text += view.as_str();
+        }
+        if self.auto {
+            text += "\n🤖 Autoplay";
+            if let Some(reasoning) = &self.reasoning {
+                text += ": ";
+                text += reasoning.as_str();
+            }
+        }
+        if let Some((_, p)) = self.hint {
+            if p == 0.0 {
+                text += "\n💡 marks a safe cell";
+            } else {
+                text += format!("\n💡 No safe cell, best guess is {:.0}% mine", p * 100.0).as_str();
+            }
+        }
+        if self.get_state() != Normal {
+            if self.timed_out {
+                text += "\n⏱ Time's up!";
+            }
+            if let Some(post_mortem) = &self.post_mortem {
+                text += "\n";
+                text += post_mortem.as_str();
+            }
+            text += "\n";
+            text += self.get_stats().as_str();
+            text += format!("\nSeed: {}", self.seed).as_str();
+            text += format!("\nCode: {}", Layout::of(&self.field, &self.opening).encode()).as_str();
+        } else if let Some(limit) = self.time_limit {
+            text += format!("\n⏱ {}s left", limit.saturating_sub(self.elapsed()).as_secs()).as_str();
+        }
+        text
     }
 
     fn to_inline_keyboard(&self) -> InlineKeyboardMarkup {
-        self.field.iter()
-            .enumerate()
-            .map(|(i, row)| row.iter()
-                .enumerate()
-                .map(|(j, c)| InlineKeyboardButton::callback(to_string(c), format!("{} {}", i, j)))
-                .collect()
-            ).collect::<Vec<Vec<_>>>().into()
+        let hint = self.hint.map(|(c, _)| c);
+        let mut keyboard = self.view.render(|coord| if hint == Some(coord) {
+            "💡".to_owned()
+        } else if let Some(marker) = self.objective.marker(&self.field, self.moves, coord) {
+            marker.to_owned()
+        } else if self.get_state() == GameState::GameOver {
+            reveal_string(&self.field, coord)
+        } else {
+            to_string(&self.field, coord)
+        });
+        if self.get_state() == Normal {
+            let mut controls = Vec::new();
+            if self.mode == Mode::Classic {
+                let text = if self.flagging { "🚩 Flagging" } else { "⛏ Digging" };
+                controls.push(InlineKeyboardButton::callback(text, TOGGLE_FLAG));
+            }
+            if self.hints && self.objective.allows_hints() && self.field.initialized {
+                controls.push(InlineKeyboardButton::callback("💡 Hint", HINT));
+            }
+            if !controls.is_empty() {
+                keyboard.push(controls);
+            }
+        } else if self.start.is_some() && !self.record.is_empty() {
+            // handled by the replay taking over once the game has ended
+            keyboard.push(vec![
+                InlineKeyboardButton::callback("🎞 Replay", REPLAY),
+                InlineKeyboardButton::callback("👥 Credits", CREDITS),
+            ]);
+        }
+        keyboard.into()
     }
 
-    fn interact(&mut self, coord: Coord) -> bool {
-        if !self.field.initialized {
-            self.field.initialize(coord);
+    fn interact(&mut self, coord: Coord, user: &str) -> bool {
+        // the time may run out between ticks, which end the game soon enough
+        if self.auto || self.time_limit.filter(|&limit| self.elapsed() >= limit).is_some() {
+            return false;
         }
-        if self.field[coord].state == State::Covered {
-            self.field.uncover(coord);
+        let action = match self.field[coord].state {
+            State::Covered | State::Flagged(_) if self.flagging => Action::Flag,
+            // flags guard their cells from being dug
+            State::Flagged(_) => return false,
+            State::Uncovered | State::Exploded if self.mode == Mode::Classic => Action::Chord,
+            _ => Action::Dig,
+        };
+        self.play(coord, action, user)
+    }
+
+    fn press(&mut self, button: &str) -> bool {
+        if button == TOGGLE_FLAG && self.mode == Mode::Classic && self.get_state() == Normal {
+            self.flagging = !self.flagging;
             true
         } else {
-            self.mode == Mode::Classic && self.field.uncover_around(coord)
+            self.view.pan(button)
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        match self.time_limit {
+            Some(limit) if !self.timed_out && self.get_state() == Normal && self.elapsed() >= limit => {
+                self.timed_out = true;
+                self.finished = Some(limit);
+                true
+            }
+            // the countdown is shown again every 5 seconds, and every second near the end
+            Some(limit) if self.started.is_some() && self.get_state() == Normal => {
+                let left = limit.saturating_sub(self.elapsed()).as_secs();
+                let due = match self.countdown {
+                    Some(shown) => shown >= left + 5 || left < 10 && shown != left,
+                    None => true,
+                };
+                if due {
+                    self.countdown = Some(left);
+                }
+                let moved = self.auto && self.autoplay_tick();
+                due || moved
+            }
+            _ => self.auto && self.get_state() == Normal && self.autoplay_tick(),
+        }
+    }
+
+    fn mistakes(&self) -> u32 {
+        self.objective.mistakes(&self.field)
+    }
+
+    fn cleared(&self) -> u32 {
+        self.field.stats.uncovered_blank
+    }
+
+    fn replay(&self) -> Option<Box<dyn game::Game>> {
+        let start = self.start.clone()?;
+        (!self.record.is_empty()).then(|| {
+            Box::new(Replay::new(start, self.record.clone(), self.get_state() == GameState::Solved)) as _
+        })
+    }
+
+    fn hint(&mut self) -> bool {
+        if !self.hints || !self.objective.allows_hints() || self.hint.is_some() || !self.field.initialized || self.get_state() != Normal {
+            return false;
         }
+        self.hint = solver::analyze(&self.field).and_then(|analysis| match analysis.safe.first() {
+            Some(&coord) => Some((coord, 0.0)),
+            None => analysis.safest(),
+        });
+        if let Some((coord, _)) = self.hint {
+            self.view.show(coord);
+        }
+        self.hint.is_some()
     }
 }
 
-fn to_string<'a>(cell: &Cell) -> &'a str {
+pub fn to_string(field: &Field, coord: Coord) -> String {
     use State::*;
     use CellValue::*;
-    match cell.state {
-        Covered => "■",
-        Exploded => "💣",
-        Uncovered => match cell.value {
-            Mine => "🚩",
-            Number(n) => {
-                let n = n as usize;
-                &" 123456789"[n..n+1]
-            }
-        }
+    let cell = &field[coord];
+    match (cell.state, cell.value) {
+        (Covered, _) => "■".to_owned(),
+        (Flagged(weight), _) => with_weight("🚩", weight),
+        (Exploded, Mine(weight)) => with_weight("💣", weight),
+        (Exploded, _) => "💣".to_owned(),
+        (Uncovered, Mine(weight)) => with_weight("🚩", weight),
+        // a zero may still have negative mines around it
+        (Uncovered, Number(_)) if field.is_blank(coord) => " ".to_owned(),
+        // larger kernels can count past 9
+        (Uncovered, Number(n)) => n.to_string(),
+    }
+}
+
+// once the game is lost: every mine, the ones that blew up, and the flags that were wrong
+fn reveal_string(field: &Field, coord: Coord) -> String {
+    use State::*;
+    use CellValue::*;
+    let cell = &field[coord];
+    match (cell.state, cell.value) {
+        (Covered, Mine(weight)) => with_weight("💣", weight),
+        (Exploded, Mine(weight)) => with_weight("💥", weight),
+        (Flagged(_), Number(_)) => "❌".to_owned(),
+        (Flagged(flags), Mine(weight)) if flags != weight => "❌".to_owned(),
+        _ => to_string(field, coord),
+    }
+}
+
+// single mines are drawn as usual
+fn with_weight(symbol: &str, weight: i32) -> String {
+    if weight == 1 {
+        symbol.to_owned()
+    } else {
+        format!("{}{}", symbol, weight)
     }
 }
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::collections::vec_deque::VecDeque;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use telegram_bot::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+use crate::game::Coord;
+use crate::grid_game::{GameState, GridGame};
+use super::game::{OptionError, TOGGLE_FLAG};
+use super::viewport::Viewport;
+
+// chunks are squares of this many cells a side
+const CHUNK: i32 = 8;
+// the most cells uncovered at once, an opening larger than this carries on over the next ticks
+const FLOOD_LIMIT: usize = 1000;
+// below about 10% mines, zeros join up into openings without end
+const MIN_PERCENTAGE: u32 = 15;
+const MAX_PERCENTAGE: u32 = 40;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Tile {
+    Covered,
+    Flagged,
+    // with the number of mines around it
+    Uncovered(u32),
+    Exploded,
+}
+
+struct Chunk {
+    mines: [bool; (CHUNK * CHUNK) as usize],
+    tiles: [Tile; (CHUNK * CHUNK) as usize],
+}
+
+// A field without edges, generated a chunk at a time when its cells are first needed.  The mines
+// of a chunk depend only on the seed and where the chunk is, so everyone sees the same field
+// whichever way they pan.
+struct InfiniteField {
+    seed: u64,
+    density: f64,
+    chunks: HashMap<(i32, i32), Chunk>,
+    cleared: u32,
+    exploded: bool,
+    // cells an opening has yet to reach
+    pending: VecDeque<Coord>,
+}
+
+// the chunk of a cell and its index within the chunk
+fn split(coord: Coord) -> ((i32, i32), usize) {
+    let chunk = (coord.0.div_euclid(CHUNK), coord.1.div_euclid(CHUNK));
+    let index = coord.0.rem_euclid(CHUNK) * CHUNK + coord.1.rem_euclid(CHUNK);
+    (chunk, index as _)
+}
+
+impl InfiniteField {
+    fn chunk(&mut self, key: (i32, i32)) -> &mut Chunk {
+        let (seed, density) = (self.seed, self.density);
+        self.chunks.entry(key).or_insert_with(|| {
+            // any mix of the seed and position will do as long as neighboring chunks differ
+            let chunk_seed = seed
+                ^ (key.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                ^ (key.1 as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+            let mut rng = ChaCha8Rng::seed_from_u64(chunk_seed);
+            let mut chunk = Chunk {
+                mines: [false; (CHUNK * CHUNK) as usize],
+                tiles: [Tile::Covered; (CHUNK * CHUNK) as usize],
+            };
+            for (i, mine) in chunk.mines.iter_mut().enumerate() {
+                let coord = Coord(key.0 * CHUNK + i as i32 / CHUNK, key.1 * CHUNK + i as i32 % CHUNK);
+                // the game starts with an opening at the origin
+                let start = coord.0.abs() <= 1 && coord.1.abs() <= 1;
+                *mine = rng.gen_bool(density) && !start;
+            }
+            chunk
+        })
+    }
+
+    fn is_mine(&mut self, coord: Coord) -> bool {
+        let (key, i) = split(coord);
+        self.chunk(key).mines[i]
+    }
+
+    fn tile(&self, coord: Coord) -> Tile {
+        let (key, i) = split(coord);
+        self.chunks.get(&key).map_or(Tile::Covered, |chunk| chunk.tiles[i])
+    }
+
+    fn set_tile(&mut self, coord: Coord, tile: Tile) {
+        let (key, i) = split(coord);
+        self.chunk(key).tiles[i] = tile;
+    }
+
+    fn neighborhood(center: Coord) -> impl Iterator<Item=Coord> {
+        Coord::DIRECTIONS.iter().map(move |&d| center + d)
+    }
+
+    // returns true if the field has changed
+    fn uncover(&mut self, coord: Coord) -> bool {
+        if self.tile(coord) != Tile::Covered {
+            return false;
+        }
+        if self.is_mine(coord) {
+            self.set_tile(coord, Tile::Exploded);
+            self.exploded = true;
+            return true;
+        }
+        self.pending.push_back(coord);
+        self.flood();
+        true
+    }
+
+    // uncovers what the openings reach, up to FLOOD_LIMIT cells; returns true if the field has changed
+    fn flood(&mut self) -> bool {
+        let mut uncovered = 0;
+        while uncovered < FLOOD_LIMIT {
+            let coord = match self.pending.pop_front() {
+                Some(coord) => coord,
+                None => break,
+            };
+            if self.tile(coord) != Tile::Covered {
+                continue;
+            }
+            let number = Self::neighborhood(coord).filter(|&c| self.is_mine(c)).count() as u32;
+            self.set_tile(coord, Tile::Uncovered(number));
+            self.cleared += 1;
+            uncovered += 1;
+            if number == 0 {
+                self.pending.extend(Self::neighborhood(coord));
+            }
+        }
+        uncovered > 0
+    }
+
+    fn toggle_flag(&mut self, coord: Coord) -> bool {
+        match self.tile(coord) {
+            Tile::Covered => self.set_tile(coord, Tile::Flagged),
+            Tile::Flagged => self.set_tile(coord, Tile::Covered),
+            _ => return false,
+        }
+        true
+    }
+}
+
+// Endless minesweeper: clear as many cells as possible before hitting a mine
+pub struct Game {
+    field: InfiniteField,
+    flagging: bool,
+    view: Viewport,
+}
+
+impl Game {
+    // "/infinite [percentage of mines] [seed=...]"
+    pub fn from_message(data: &str) -> Result<Self, OptionError> {
+        let mut seed = rand::random();
+        let mut percentage = 18;
+        for arg in data.split_whitespace().skip(1) {
+            if let Some(value) = arg.strip_prefix("seed=") {
+                seed = value.parse().map_err(|_| OptionError::Seed(value.to_owned()))?;
+            } else if let Ok(num) = arg.parse::<u32>() {
+                percentage = num.clamp(MIN_PERCENTAGE, MAX_PERCENTAGE);
+            }
+        }
+        let mut field = InfiniteField {
+            seed,
+            density: percentage as f64 / 100.0,
+            chunks: HashMap::new(),
+            cleared: 0,
+            exploded: false,
+            pending: VecDeque::new(),
+        };
+        field.uncover(Coord(0, 0));
+        Ok(Self { field, flagging: false, view: Viewport::unbounded() })
+    }
+}
+
+impl GridGame for Game {
+    fn get_state(&self) -> GameState {
+        if self.field.exploded { GameState::GameOver } else { GameState::Normal }
+    }
+
+    fn get_text(&self) -> String {
+        let mut text = format!("Endless minesweeper, {:.0}% mines\nCleared {} cells",
+            self.field.density * 100.0, self.field.cleared);
+        if let Some(view) = self.view.describe() {
+            text += "\n";
+            text += view.as_str();
+        }
+        if self.field.exploded {
+            text += format!("\nSeed: {}", self.field.seed).as_str();
+        }
+        text
+    }
+
+    fn to_inline_keyboard(&self) -> InlineKeyboardMarkup {
+        let mut keyboard = self.view.render(|coord| match self.field.tile(coord) {
+            Tile::Covered => "■".to_owned(),
+            Tile::Flagged => "🚩".to_owned(),
+            Tile::Uncovered(0) => " ".to_owned(),
+            Tile::Uncovered(n) => n.to_string(),
+            Tile::Exploded => "💣".to_owned(),
+        });
+        if !self.field.exploded {
+            let text = if self.flagging { "🚩 Flagging" } else { "⛏ Digging" };
+            keyboard.push(vec![InlineKeyboardButton::callback(text, TOGGLE_FLAG)]);
+        }
+        keyboard.into()
+    }
+
+    fn interact(&mut self, coord: Coord, _user: &str) -> bool {
+        if self.flagging {
+            self.field.toggle_flag(coord)
+        } else {
+            self.field.uncover(coord)
+        }
+    }
+
+    fn press(&mut self, button: &str) -> bool {
+        if button == TOGGLE_FLAG && !self.field.exploded {
+            self.flagging = !self.flagging;
+            true
+        } else {
+            self.view.pan(button)
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        !self.field.exploded && self.field.flood()
+    }
+
+    fn cleared(&self) -> u32 {
+        self.field.cleared
+    }
+}
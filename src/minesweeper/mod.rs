@@ -1,2 +1,18 @@
+pub mod code;
+pub mod field;
+pub mod solver;
+pub mod topology;
+#[cfg(feature = "bot")]
+pub mod editor;
+#[cfg(feature = "bot")]
+pub mod flags;
+#[cfg(feature = "bot")]
 pub mod game;
-mod field;
+#[cfg(feature = "bot")]
+pub mod infinite;
+#[cfg(feature = "bot")]
+mod objective;
+#[cfg(feature = "bot")]
+mod replay;
+#[cfg(feature = "bot")]
+mod viewport;
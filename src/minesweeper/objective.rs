@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::Coord;
+use crate::grid_game::GameState;
+use crate::grid_game::GameState::{GameOver, Normal, Solved};
+use super::field::{Field, Multiplicity, State};
+
+// Decides the outcome of a game from the stats of its field, see MineFieldStats
+pub trait Objective: Send {
+    fn get_state(&self, field: &Field, moves: u32) -> GameState;
+    fn get_text(&self, field: &Field, moves: u32) -> String;
+    // called once the mines have been laid, before the first click is uncovered; false if the
+    // objective can't be met on this layout
+    fn initialize(&mut self, _field: &Field, _start: Coord, _rng: &mut ChaCha8Rng) -> bool { true }
+    // what to draw on a cell instead of its usual look
+    fn marker(&self, _field: &Field, _moves: u32, _coord: Coord) -> Option<&'static str> { None }
+    // whether pointing out safe cells helps
+    fn allows_hints(&self) -> bool { true }
+    // added to the time taken
+    fn penalty(&self, _field: &Field) -> Duration { Duration::default() }
+    // mines hit that the game carried on after
+    fn mistakes(&self, field: &Field) -> u32 { field.stats.exploded }
+}
+
+// the mine counter goes by flags, so it can go negative if too many are placed
+fn mines_left(field: &Field) -> String {
+    if !field.initialized && field.multiplicity != Multiplicity::Single {
+        // the weights aren't known before the first click
+        return format!("{} mined cells", field.mines);
+    }
+    let total = field.mine_total();
+    format!("{} left / {} mines", total - field.mines_marked(), total)
+}
+
+// Clear every cell that is not a mine, the classic game
+pub struct Clear {
+    pub lives: u32,
+}
+
+impl Objective for Clear {
+    fn get_state(&self, field: &Field, _moves: u32) -> GameState {
+        let stats = &field.stats;
+        if stats.exploded >= self.lives {
+            GameOver
+        } else if stats.uncovered_blank + field.mines == field.size.size() {
+            Solved
+        } else {
+            Normal
+        }
+    }
+
+    fn get_text(&self, field: &Field, _moves: u32) -> String {
+        let mut text = mines_left(field);
+        if self.lives > 1 {
+            let lives = self.lives.saturating_sub(field.stats.exploded);
+            text += format!("\n❤️ {} / {} lives", lives, self.lives).as_str();
+        }
+        text
+    }
+}
+
+// Clear every cell that is not a mine, where hitting one costs time instead of ending the game
+pub struct Penalty {
+    pub seconds: u64,
+}
+
+impl Objective for Penalty {
+    fn get_state(&self, field: &Field, _moves: u32) -> GameState {
+        if field.stats.uncovered_blank + field.mines == field.size.size() {
+            Solved
+        } else {
+            Normal
+        }
+    }
+
+    fn get_text(&self, field: &Field, _moves: u32) -> String {
+        let mut text = mines_left(field);
+        if field.stats.exploded > 0 {
+            text += format!("\n💥 {} mines hit, +{}s", field.stats.exploded,
+                self.penalty(field).as_secs()).as_str();
+        }
+        text
+    }
+
+    fn penalty(&self, field: &Field) -> Duration {
+        Duration::from_secs(self.seconds * field.stats.exploded as u64)
+    }
+}
+
+// Clear a given number of cells without hitting a mine
+pub struct ClearCells {
+    pub cells: u32,
+}
+
+impl Objective for ClearCells {
+    fn get_state(&self, field: &Field, _moves: u32) -> GameState {
+        let stats = &field.stats;
+        if stats.exploded > 0 {
+            GameOver
+        } else if stats.uncovered_blank >= self.cells.min(field.size.size() - field.mines) {
+            Solved
+        } else {
+            Normal
+        }
+    }
+
+    fn get_text(&self, field: &Field, _moves: u32) -> String {
+        format!("{}\nCleared {} / {} cells", mines_left(field), field.stats.uncovered_blank, self.cells)
+    }
+}
+
+// Find mines by tapping them, within a limited number of moves
+pub struct Tap {
+    pub mines: u32,
+    pub moves: u32,
+}
+
+impl Objective for Tap {
+    fn get_state(&self, field: &Field, moves: u32) -> GameState {
+        if field.stats.exploded >= self.mines.min(field.mines) {
+            Solved
+        } else if moves >= self.moves {
+            GameOver
+        } else {
+            Normal
+        }
+    }
+
+    fn get_text(&self, field: &Field, moves: u32) -> String {
+        format!("🎯 Found {} / {} mines\n{} moves left", field.stats.exploded, self.mines,
+            self.moves.saturating_sub(moves))
+    }
+
+    fn marker(&self, field: &Field, _moves: u32, coord: Coord) -> Option<&'static str> {
+        (field[coord].state == State::Exploded).then_some("🎯")
+    }
+
+    fn allows_hints(&self) -> bool { false }
+
+    // hitting mines is the point
+    fn mistakes(&self, _field: &Field) -> u32 { 0 }
+}
+
+// Uncover the one cell hiding the treasure
+#[derive(Default)]
+pub struct Treasure {
+    cell: Option<Coord>,
+}
+
+impl Objective for Treasure {
+    fn get_state(&self, field: &Field, _moves: u32) -> GameState {
+        let found = match self.cell {
+            Some(cell) => field[cell].state == State::Uncovered,
+            // there was nowhere to hide it, as the first click cleared the board
+            None => field.stats.uncovered_blank + field.mines == field.size.size(),
+        };
+        if field.stats.exploded > 0 {
+            GameOver
+        } else if found {
+            Solved
+        } else {
+            Normal
+        }
+    }
+
+    fn get_text(&self, field: &Field, _moves: u32) -> String {
+        format!("{}\nFind the hidden 💎!", mines_left(field))
+    }
+
+    fn initialize(&mut self, field: &Field, start: Coord, rng: &mut ChaCha8Rng) -> bool {
+        // hide it somewhere the first click doesn't reach
+        let mut opened = field.clone();
+        opened.uncover(start);
+        let candidates: Vec<_> = field.size.valid_indices()
+            .filter(|&c| opened[c].is_covered() && !opened[c].value.is_mine())
+            .collect();
+        self.cell = candidates.choose(rng).copied();
+        self.cell.is_some()
+    }
+
+    fn marker(&self, field: &Field, moves: u32, coord: Coord) -> Option<&'static str> {
+        (self.cell == Some(coord) && (field[coord].state == State::Uncovered
+            || self.get_state(field, moves) != Normal)).then_some("💎")
+    }
+}
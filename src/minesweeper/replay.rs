@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use itertools::Itertools;
+use telegram_bot::{InlineKeyboardButton, InlineKeyboardMarkup, User};
+
+use crate::game::{Coord, Game, InteractResult};
+use super::field::{Field, Multiplicity, State};
+use super::game::to_string;
+use super::topology::Topology;
+use super::viewport::Viewport;
+
+// callback data of the replay controls
+pub const REPLAY: &str = "replay";
+const PREVIOUS: &str = "prev";
+const NEXT: &str = "next";
+const LAST: &str = "last";
+const EXPORT: &str = "export";
+pub const CREDITS: &str = "credits";
+
+// marks the cells cleared by each player, in order of their first clear
+const PALETTE: [&str; 8] = ["🟥", "🟦", "🟩", "🟨", "🟪", "🟧", "🟫", "⬛"];
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    Dig,
+    Flag,
+    Chord,
+}
+
+impl Action {
+    // returns true if the field has changed
+    pub fn apply(self, field: &mut Field, coord: Coord) -> bool {
+        match self {
+            Action::Dig => field[coord].state == State::Covered && {
+                field.uncover(coord);
+                true
+            },
+            Action::Flag => field.toggle_flag(coord),
+            Action::Chord => field.uncover_around(coord),
+        }
+    }
+}
+
+// A click on the board, including those that didn't change anything
+#[derive(Clone)]
+pub struct Move {
+    pub coord: Coord,
+    pub action: Action,
+    pub user: String,
+    // since the first click
+    pub time: Duration,
+}
+
+// Steps through a finished game, which keeps its message
+pub struct Replay {
+    // the board before the first move, with its mines already laid
+    start: Field,
+    moves: Vec<Move>,
+    solved: bool,
+    // how many moves have been made on the board shown
+    step: usize,
+    field: Field,
+    view: Viewport,
+    // who cleared each cell, by cell index into the players in order of their first clear
+    clearers: Vec<String>,
+    cleared_by: Vec<Option<usize>>,
+    // showing who cleared what instead of the board
+    credits: bool,
+}
+
+impl Replay {
+    pub fn new(start: Field, moves: Vec<Move>, solved: bool) -> Self {
+        let view = Viewport::new(start.size, start.topology.kernel);
+        let mut replay = Self {
+            field: start.clone(),
+            cleared_by: vec![None; start.size.size() as _],
+            clearers: Vec::new(),
+            start,
+            step: 0,
+            moves,
+            solved,
+            view,
+            credits: false,
+        };
+        replay.credit();
+        replay.seek(replay.moves.len());
+        replay
+    }
+
+    // plays the game through once to find out who cleared each cell, flood fills included
+    fn credit(&mut self) {
+        let mut field = self.start.clone();
+        for m in &self.moves {
+            let cleared = field.stats.uncovered_blank;
+            if !m.action.apply(&mut field, m.coord) || field.stats.uncovered_blank == cleared {
+                continue;
+            }
+            let player = match self.clearers.iter().position(|user| *user == m.user) {
+                Some(player) => player,
+                None => {
+                    self.clearers.push(m.user.clone());
+                    self.clearers.len() - 1
+                }
+            };
+            for coord in field.size.valid_indices() {
+                let i = field.size.index(coord);
+                if field[coord].state == State::Uncovered && !field[coord].value.is_mine() && self.cleared_by[i].is_none() {
+                    self.cleared_by[i] = Some(player);
+                }
+            }
+        }
+    }
+
+    fn seek(&mut self, step: usize) {
+        if step < self.step {
+            self.field = self.start.clone();
+            self.step = 0;
+        }
+        for m in &self.moves[self.step..step] {
+            m.action.apply(&mut self.field, m.coord);
+        }
+        self.step = step;
+        if let Some(m) = step.checked_sub(1).map(|i| &self.moves[i]) {
+            self.view.show(m.coord);
+        }
+    }
+
+    fn get_text(&self) -> String {
+        if self.credits {
+            let mut text = "👥 Who cleared what".to_owned();
+            for (player, user) in self.clearers.iter().enumerate() {
+                let cells = self.cleared_by.iter().filter(|&&p| p == Some(player)).count();
+                text += format!("\n{} {} - {} cells", PALETTE[player % PALETTE.len()], user, cells).as_str();
+            }
+            return text;
+        }
+        let mut text = format!("🎞 Replay, move {} / {}", self.step, self.moves.len());
+        if let Some(m) = self.step.checked_sub(1).map(|i| &self.moves[i]) {
+            let action = match m.action {
+                Action::Dig => "dug",
+                Action::Flag => "flagged",
+                Action::Chord => "chorded",
+            };
+            text += format!("\n{} {} {}, {} at {:.1}s", m.user, action, m.coord.0, m.coord.1,
+                m.time.as_secs_f64()).as_str();
+        }
+        text
+    }
+
+    fn to_inline_keyboard(&self) -> InlineKeyboardMarkup {
+        let mut keyboard = self.view.render(|coord| match self.cleared_by[self.field.size.index(coord)] {
+            Some(player) if self.credits => PALETTE[player % PALETTE.len()].to_owned(),
+            _ => to_string(&self.field, coord),
+        });
+        let mut controls = vec![
+            InlineKeyboardButton::callback("⏮", REPLAY),
+            InlineKeyboardButton::callback("◀", PREVIOUS),
+            InlineKeyboardButton::callback("▶", NEXT),
+            InlineKeyboardButton::callback("⏭", LAST),
+            InlineKeyboardButton::callback("👥", CREDITS),
+        ];
+        if self.exportable() {
+            controls.push(InlineKeyboardButton::callback("📤", EXPORT));
+        }
+        keyboard.push(controls);
+        keyboard.into()
+    }
+
+    // other viewers only know the classic board
+    fn exportable(&self) -> bool {
+        self.start.topology == Topology::default() && self.start.multiplicity == Multiplicity::Single
+    }
+
+    // The game in the RAW Viewer Format of Minesweeper Arbiter and similar tools.  Clicks are
+    // made in the middle of the 16 pixel squares of those viewers.
+    fn export(&self) -> String {
+        let field = &self.start;
+        let board = field.iter()
+            .map(|row| row.iter().map(|c| if c.value.is_mine() { '*' } else { '0' }).collect::<String>())
+            .join("\n");
+        let mut text = format!("RawVF_Version: Rev5\nProgram: minesweeper_bot\nPlayer: {}\nLevel: Custom\n\
+            Width: {}\nHeight: {}\nMines: {}\nMarks: Off\nTime: {:.2}\nBBBV: {}\nBoard:\n{}\nEvents:\n0.00 start\n",
+            self.moves.iter().map(|m| m.user.as_str()).unique().join(", "),
+            field.size.1, field.size.0, field.mines,
+            self.moves.last().map_or(0.0, |m| m.time.as_secs_f64()),
+            field.bbbv().1, board);
+        for m in &self.moves {
+            let (press, release) = match m.action {
+                Action::Dig => ("lc", "lr"),
+                Action::Flag => ("rc", "rr"),
+                Action::Chord => ("mc", "mr"),
+            };
+            let Coord(row, column) = m.coord;
+            for event in &[press, release] {
+                text += format!("{:.2} {} {} {} ({} {})\n", m.time.as_secs_f64(), event,
+                    column * 16 + 8, row * 16 + 8, column + 1, row + 1).as_str();
+            }
+        }
+        text += format!("{:.2} {}\n", self.moves.last().map_or(0.0, |m| m.time.as_secs_f64()),
+            if self.solved { "won" } else { "blast" }).as_str();
+        text
+    }
+}
+
+impl Game for Replay {
+    fn interact(&mut self, _coord: Coord, _user: &User) -> Option<InteractResult> {
+        None
+    }
+
+    fn press(&mut self, button: &str, _user: &User) -> Option<InteractResult> {
+        if self.view.pan(button) {
+            return Some(InteractResult {
+                update_text: None,
+                update_board: Some(self.to_inline_keyboard()),
+                game_end: false,
+                new_game: None,
+                reply: None,
+            });
+        }
+        if button == CREDITS {
+            // the credits are of the whole game, so they come with its final board
+            self.credits = !self.credits;
+            self.seek(self.moves.len());
+            return Some(InteractResult {
+                update_text: Some(self.get_text()),
+                update_board: Some(self.to_inline_keyboard()),
+                game_end: false,
+                new_game: None,
+                reply: None,
+            });
+        }
+        let step = match button {
+            REPLAY => 0,
+            PREVIOUS => self.step.saturating_sub(1),
+            NEXT => (self.step + 1).min(self.moves.len()),
+            LAST => self.moves.len(),
+            EXPORT if self.exportable() => return Some(InteractResult {
+                update_text: None,
+                update_board: None,
+                game_end: false,
+                new_game: None,
+                reply: Some(self.export()),
+            }),
+            _ => return None,
+        };
+        (step != self.step || self.credits).then(|| {
+            self.credits = false;
+            self.seek(step);
+            InteractResult {
+                update_text: Some(self.get_text()),
+                update_board: Some(self.to_inline_keyboard()),
+                game_end: false,
+                new_game: None,
+                reply: None,
+            }
+        })
+    }
+}
@@ -0,0 +1,624 @@
+use rand::Rng;
+
+use crate::coord::{Coord, Size};
+use super::field::{CellValue, Field, Multiplicity, State};
+
+// Enumerating solutions takes exponential time on large frontiers, so every analysis may only take
+// this many steps of the search, after which it settles for what single numbers give away
+pub const SEARCH_BUDGET: u32 = 200_000;
+
+// Everything a player can deduce about the covered cells of a field.  Only what a player can see
+// is consulted: the numbers on uncovered cells, revealed mines and the total number of mines; the
+// value hidden under a covered cell is never looked at, and flags are not trusted.
+pub struct Analysis {
+    pub safe: Vec<Coord>,
+    pub mines: Vec<Coord>,
+    size: Size,
+    // indexed like the field; None for cells that are not covered
+    probabilities: Box<[Option<f64>]>,
+}
+
+impl Analysis {
+    // chance that a covered cell holds a mine
+    pub fn probability(&self, coord: Coord) -> Option<f64> {
+        self.probabilities[self.size.index(coord)]
+    }
+
+    // the covered cell least likely to hold a mine
+    pub fn safest(&self) -> Option<(Coord, f64)> {
+        self.size.valid_indices()
+            .filter_map(|c| self.probability(c).map(|p| (c, p)))
+            .fold(None, |best, (c, p)| match best {
+                Some((_, q)) if q <= p => best,
+                _ => Some((c, p)),
+            })
+    }
+}
+
+// A number cell: `mines` of the covered cells around it (the component's variables) are mines
+struct Constraint {
+    cells: Vec<usize>,
+    mines: u32,
+}
+
+// Covered cells that share constraints, i.e. whose contents depend on each other
+struct Component {
+    cells: Vec<Coord>,
+    constraints: Vec<Constraint>,
+}
+
+// Solutions of a component, counted separately by how many mines they place
+struct Solutions {
+    // count[k]: number of solutions placing k mines
+    count: Vec<f64>,
+    // mine[k][i]: how many of those have a mine on cell i
+    mine: Vec<Vec<f64>>,
+}
+
+struct Search<'a> {
+    component: &'a Component,
+    cell_constraints: Vec<Vec<usize>>,
+    assignment: Vec<bool>,
+    assigned_mines: Vec<u32>,
+    unassigned: Vec<u32>,
+    solutions: Solutions,
+    // steps left before giving up
+    budget: u32,
+}
+
+impl<'a> Search<'a> {
+    fn new(component: &'a Component, budget: u32) -> Self {
+        let n = component.cells.len();
+        let mut cell_constraints = vec![Vec::new(); n];
+        for (i, constraint) in component.constraints.iter().enumerate() {
+            for &cell in &constraint.cells {
+                cell_constraints[cell].push(i);
+            }
+        }
+        Self {
+            component,
+            cell_constraints,
+            assignment: vec![false; n],
+            assigned_mines: vec![0; component.constraints.len()],
+            unassigned: component.constraints.iter().map(|c| c.cells.len() as u32).collect(),
+            solutions: Solutions {
+                count: vec![0.0; n + 1],
+                mine: vec![vec![0.0; n]; n + 1],
+            },
+            budget,
+        }
+    }
+
+    fn step(&mut self) -> bool {
+        self.budget = self.budget.saturating_sub(1);
+        self.budget > 0
+    }
+
+    // assigns a cell and returns whether every constraint it takes part in can still be met
+    fn assign(&mut self, cell: usize, mine: bool) -> bool {
+        self.assignment[cell] = mine;
+        let mut valid = true;
+        for &i in &self.cell_constraints[cell] {
+            self.unassigned[i] -= 1;
+            if mine {
+                self.assigned_mines[i] += 1;
+            }
+            let required = self.component.constraints[i].mines;
+            valid &= self.assigned_mines[i] <= required
+                && self.assigned_mines[i] + self.unassigned[i] >= required;
+        }
+        valid
+    }
+
+    fn unassign(&mut self, cell: usize) {
+        let mine = self.assignment[cell];
+        for &i in &self.cell_constraints[cell] {
+            self.unassigned[i] += 1;
+            if mine {
+                self.assigned_mines[i] -= 1;
+            }
+        }
+    }
+
+    // returns false if the budget ran out before every solution was counted
+    fn search(&mut self, cell: usize, mines: usize) -> bool {
+        if !self.step() {
+            return false;
+        }
+        if cell == self.assignment.len() {
+            self.solutions.count[mines] += 1.0;
+            for (i, &mine) in self.assignment.iter().enumerate() {
+                if mine {
+                    self.solutions.mine[mines][i] += 1.0;
+                }
+            }
+            return true;
+        }
+        for &mine in &[false, true] {
+            let complete = !self.assign(cell, mine) || self.search(cell + 1, mines + mine as usize);
+            self.unassign(cell);
+            if !complete {
+                return false;
+            }
+        }
+        true
+    }
+
+    // goes through the solutions placing exactly `target` mines in the order search counts them,
+    // passing over `skip` of them, and leaves the next one in the assignment
+    fn nth(&mut self, cell: usize, mines: usize, target: usize, skip: &mut u64) -> bool {
+        if !self.step() || mines > target || mines + self.assignment.len() - cell < target {
+            return false;
+        }
+        if cell == self.assignment.len() {
+            if *skip == 0 {
+                return true;
+            }
+            *skip -= 1;
+            return false;
+        }
+        for &mine in &[false, true] {
+            if self.assign(cell, mine) && self.nth(cell + 1, mines + mine as usize, target, skip) {
+                return true;
+            }
+            self.unassign(cell);
+        }
+        false
+    }
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+fn ln_binomial(n: u32, k: u32) -> f64 {
+    (0..k).map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln()).sum()
+}
+
+// Splits the covered cells next to numbers into independent components.  Returns None if some
+// number can't be satisfied at all, which can only happen on hand-made boards.
+fn components(field: &Field, known_mine: impl Fn(Coord) -> bool) -> Option<(Vec<Component>, Vec<Coord>)> {
+    let size = field.size;
+    let is_unknown = |c: Coord| field[c].is_covered();
+
+    // constraints over field coordinates first
+    let mut constraints = Vec::new();
+    for coord in size.valid_indices() {
+        if let (State::Uncovered, CellValue::Number(n)) = (field[coord].state, field[coord].value) {
+            let n = n as u32;
+            let cells: Vec<_> = field.neighborhood(coord).filter(|&c| is_unknown(c)).collect();
+            let known = field.neighborhood(coord).filter(|&c| known_mine(c)).count() as u32;
+            if known > n || known + (cells.len() as u32) < n {
+                return None;
+            }
+            if !cells.is_empty() {
+                constraints.push((cells, n - known));
+            }
+        }
+    }
+
+    // then group them by breadth-first search over shared cells
+    let mut constraints_on = vec![Vec::new(); size.size() as _];
+    for (i, (cells, _)) in constraints.iter().enumerate() {
+        for &c in cells {
+            constraints_on[size.index(c)].push(i);
+        }
+    }
+    let mut component_of = vec![None; size.size() as _];
+    let mut constraint_used = vec![false; constraints.len()];
+    let mut result = Vec::new();
+    for start in 0..constraints.len() {
+        if constraint_used[start] {
+            continue;
+        }
+        constraint_used[start] = true;
+        let mut component = Component { cells: Vec::new(), constraints: Vec::new() };
+        let mut queue = vec![start];
+        let mut next = 0;
+        while next < queue.len() {
+            let (cells, mines) = &constraints[queue[next]];
+            next += 1;
+            let mut local = Vec::with_capacity(cells.len());
+            for &c in cells {
+                let i = size.index(c);
+                if component_of[i].is_none() {
+                    component_of[i] = Some(component.cells.len());
+                    component.cells.push(c);
+                    // pull in every other constraint on this cell
+                    for &j in &constraints_on[i] {
+                        if !constraint_used[j] {
+                            constraint_used[j] = true;
+                            queue.push(j);
+                        }
+                    }
+                }
+                local.push(component_of[i].unwrap());
+            }
+            component.constraints.push(Constraint { cells: local, mines: *mines });
+        }
+        result.push(component);
+    }
+
+    let interior = size.valid_indices()
+        .filter(|&c| is_unknown(c) && component_of[size.index(c)].is_none())
+        .collect();
+    Some((result, interior))
+}
+
+// What the numbers give away one at a time, for when the search runs out of budget: the cells
+// around a number whose mines are all known are safe, and those around a number that needs every
+// one of them are mines.  Other cells are given the density of the mines left as a rough chance.
+fn deduce_locally(field: &Field, components: &[Component], interior: &[Coord], remaining: usize) -> Analysis {
+    let size = field.size;
+    let covered = components.iter().map(|c| c.cells.len()).sum::<usize>() + interior.len();
+    let density = (remaining as f64 / covered.max(1) as f64).min(1.0);
+    let mut analysis = Analysis {
+        safe: Vec::new(),
+        mines: Vec::new(),
+        size,
+        probabilities: size.valid_indices()
+            .map(|c| field[c].is_covered().then_some(density))
+            .collect(),
+    };
+    for component in components {
+        let mut deduced = vec![None; component.cells.len()];
+        for constraint in &component.constraints {
+            if constraint.mines == 0 || constraint.mines as usize == constraint.cells.len() {
+                for &cell in &constraint.cells {
+                    deduced[cell].get_or_insert(constraint.mines > 0);
+                }
+            }
+        }
+        for (&coord, deduced) in component.cells.iter().zip(deduced) {
+            match deduced {
+                Some(false) => analysis.safe.push(coord),
+                Some(true) => analysis.mines.push(coord),
+                None => continue,
+            }
+            analysis.probabilities[size.index(coord)] = deduced.map(|mine| mine as u8 as f64);
+        }
+    }
+    analysis
+}
+
+// Returns None if no layout is consistent with what is visible on the field, or if it has mines of
+// other weights than one, which this doesn't model
+pub fn analyze(field: &Field) -> Option<Analysis> {
+    analyze_within(field, &mut SEARCH_BUDGET.clone())
+}
+
+// Like analyze, taking the steps of the search from a budget that may be shared by several calls
+fn analyze_within(field: &Field, budget: &mut u32) -> Option<Analysis> {
+    if field.multiplicity != Multiplicity::Single {
+        return None;
+    }
+    let size = field.size;
+    // besides the search, an analysis takes time in proportion to the size of the field
+    *budget = budget.saturating_sub(size.size());
+    let known_mine = |c: Coord| !field[c].is_covered() && field[c].value.is_mine();
+    let (components, interior) = components(field, known_mine)?;
+
+    let known_mines = size.valid_indices().filter(|&c| known_mine(c)).count() as u32;
+    let remaining = field.mines.checked_sub(known_mines)? as usize;
+    let interior_size = interior.len();
+
+    let mut solutions = Vec::with_capacity(components.len());
+    for component in &components {
+        let mut search = Search::new(component, *budget);
+        let complete = search.search(0, 0);
+        *budget = search.budget;
+        if !complete {
+            return Some(deduce_locally(field, &components, &interior, remaining));
+        }
+        solutions.push(search.solutions);
+    }
+    let total = solutions.iter().fold(vec![1.0], |acc, s| convolve(&acc, &s.count));
+
+    // relative number of ways to fill the interior when the frontier holds m mines, or None if
+    // the rest of the mines don't fit in the interior
+    let feasible = |m: usize| m <= remaining && remaining - m <= interior_size;
+    let max_ln = (0..total.len())
+        .filter(|&m| feasible(m))
+        .map(|m| ln_binomial(interior_size as _, (remaining - m) as _))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let interior_ways = |m: usize| feasible(m)
+        .then(|| (ln_binomial(interior_size as _, (remaining - m) as _) - max_ln).exp());
+
+    let weight: f64 = (0..total.len())
+        .filter_map(|m| interior_ways(m).map(|w| w * total[m]))
+        .sum();
+    if weight == 0.0 {
+        return None;
+    }
+
+    let mut analysis = Analysis {
+        safe: Vec::new(),
+        mines: Vec::new(),
+        size,
+        probabilities: size.valid_indices()
+            .map(|c| field[c].is_covered().then_some(0.0))
+            .collect(),
+    };
+    let record = |analysis: &mut Analysis, coord: Coord, p: f64, can_be_mine: bool, can_be_safe: bool| {
+        if !can_be_mine {
+            analysis.safe.push(coord);
+            analysis.probabilities[size.index(coord)] = Some(0.0);
+        } else if !can_be_safe {
+            analysis.mines.push(coord);
+            analysis.probabilities[size.index(coord)] = Some(1.0);
+        } else {
+            analysis.probabilities[size.index(coord)] = Some(p);
+        }
+    };
+
+    // ways for the components before and after each one to place m mines
+    let mut heads = vec![vec![1.0]];
+    for s in &solutions {
+        heads.push(convolve(heads.last().unwrap(), &s.count));
+    }
+    let mut tails = vec![vec![1.0]];
+    for s in solutions.iter().rev() {
+        tails.push(convolve(tails.last().unwrap(), &s.count));
+    }
+    tails.reverse();
+
+    for (c, (component, s)) in components.iter().zip(&solutions).enumerate() {
+        let others = convolve(&heads[c], &tails[c + 1]);
+        // ways for the rest of the field to go with k mines in this component, or None if it can't
+        let rest: Vec<_> = (0..s.count.len())
+            .map(|k| others.iter()
+                .enumerate()
+                .filter_map(|(j, &ways)| interior_ways(k + j).filter(|_| ways > 0.0).map(|w| ways * w))
+                .fold(None, |sum, w| Some(sum.unwrap_or(0.0) + w)))
+            .collect();
+        for (i, &coord) in component.cells.iter().enumerate() {
+            let mut p = 0.0;
+            let mut can_be_mine = false;
+            let mut can_be_safe = false;
+            for (k, &count) in s.count.iter().enumerate() {
+                if let Some(w) = rest[k] {
+                    let mine = s.mine[k][i];
+                    p += mine * w;
+                    can_be_mine |= mine > 0.0;
+                    can_be_safe |= count > mine;
+                }
+            }
+            record(&mut analysis, coord, p / weight, can_be_mine, can_be_safe);
+        }
+    }
+
+    if !interior.is_empty() {
+        let mut p = 0.0;
+        let mut can_be_mine = false;
+        let mut can_be_safe = false;
+        for (m, &count) in total.iter().enumerate() {
+            if let Some(w) = interior_ways(m).filter(|_| count > 0.0) {
+                let left = remaining - m;
+                p += count * w * left as f64 / interior_size as f64;
+                can_be_mine |= left > 0;
+                can_be_safe |= left < interior_size;
+            }
+        }
+        for coord in interior {
+            record(&mut analysis, coord, p / weight, can_be_mine, can_be_safe);
+        }
+    }
+
+    Some(analysis)
+}
+
+// Picks an index with probability proportional to its weight
+fn choose(weights: impl Iterator<Item=f64>, rng: &mut impl Rng) -> Option<usize> {
+    let weights: Vec<_> = weights.collect();
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut x = rng.gen_range(0.0..total);
+    for (i, &w) in weights.iter().enumerate() {
+        if x < w {
+            return Some(i);
+        }
+        x -= w;
+    }
+    weights.iter().rposition(|&w| w > 0.0)
+}
+
+// Lays the mines anew under the covered cells, agreeing with everything visible on the field and
+// with a mine on the given covered cell or not, as asked.  Every such layout is about as likely
+// as any other.  Returns where all the mines are, revealed ones included, or None if no layout
+// fits or the search runs out of budget looking for one.
+pub fn rearrange(field: &Field, coord: Coord, mine: bool, rng: &mut impl Rng) -> Option<Vec<Coord>> {
+    if field.multiplicity != Multiplicity::Single {
+        return None;
+    }
+    let size = field.size;
+    let known_mine = |c: Coord| !field[c].is_covered() && field[c].value.is_mine();
+    let (mut components, mut interior) = components(field, known_mine)?;
+    let mut mines: Vec<_> = size.valid_indices().filter(|&c| known_mine(c)).collect();
+    let mut remaining = field.mines.checked_sub(mines.len() as u32)? as usize;
+
+    // the cell in question is settled first, by a constraint of its own if it borders a number
+    if let Some(i) = interior.iter().position(|&c| c == coord) {
+        interior.swap_remove(i);
+        if mine {
+            mines.push(coord);
+            remaining = remaining.checked_sub(1)?;
+        }
+    } else {
+        let component = components.iter_mut().find(|component| component.cells.contains(&coord))?;
+        let cell = component.cells.iter().position(|&c| c == coord)?;
+        component.constraints.push(Constraint { cells: vec![cell], mines: mine as u32 });
+    }
+
+    let mut budget = SEARCH_BUDGET;
+    let mut counts = Vec::with_capacity(components.len());
+    for component in &components {
+        let mut search = Search::new(component, budget);
+        if !search.search(0, 0) {
+            return None;
+        }
+        budget = search.budget;
+        counts.push(search.solutions.count);
+    }
+    // tails[c][m]: ways for the components from c on to place m mines
+    let mut tails = vec![vec![1.0]];
+    for count in counts.iter().rev() {
+        let tail = convolve(count, tails.last().unwrap());
+        tails.push(tail);
+    }
+    tails.reverse();
+
+    // how many mines go to the frontier, weighed by the ways to fill the interior with the rest
+    let feasible = |m: usize| m <= remaining && remaining - m <= interior.len();
+    let max_ln = (0..tails[0].len())
+        .filter(|&m| feasible(m))
+        .map(|m| ln_binomial(interior.len() as _, (remaining - m) as _))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut frontier = choose((0..tails[0].len()).map(|m| if feasible(m) {
+        tails[0][m] * (ln_binomial(interior.len() as _, (remaining - m) as _) - max_ln).exp()
+    } else {
+        0.0
+    }), rng)?;
+    let left = remaining - frontier;
+
+    // then how many each component gets, and one of its solutions with that many, all as likely
+    for (c, component) in components.iter().enumerate() {
+        let k = choose(counts[c].iter().enumerate().map(|(k, &count)| {
+            let rest = &tails[c + 1];
+            if k <= frontier && frontier - k < rest.len() { count * rest[frontier - k] } else { 0.0 }
+        }), rng)?;
+        frontier -= k;
+        let mut skip = rng.gen_range(0.0..counts[c][k]) as u64;
+        let mut search = Search::new(component, budget);
+        if !search.nth(0, 0, k, &mut skip) {
+            return None;
+        }
+        budget = search.budget;
+        mines.extend(component.cells.iter()
+            .zip(&search.assignment)
+            .filter_map(|(&c, &mine)| mine.then_some(c)));
+    }
+    mines.extend(rand::seq::index::sample(rng, interior.len(), left).into_iter().map(|i| interior[i]));
+    Some(mines)
+}
+
+// Whether an initialized field can be cleared, starting from `start`, without ever guessing.  The
+// search takes its steps from the budget, and once that has run out the answer is no.
+pub fn is_solvable(field: &Field, start: Coord, budget: &mut u32) -> bool {
+    let mut field = field.clone();
+    field.uncover(start);
+    while let Some(analysis) = analyze_within(&field, budget) {
+        if analysis.safe.is_empty() || *budget == 0 {
+            break;
+        }
+        for coord in analysis.safe {
+            field.uncover(coord);
+        }
+    }
+    field.stats.uncovered_blank + field.mines == field.size.size()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::topology::Topology;
+
+    fn board(size: Size, mines: &[Coord], uncovered: &[Coord]) -> Field {
+        let mines: Vec<_> = mines.iter().map(|&c| (c, 1)).collect();
+        Field::with_layout(size, Topology::default(), &mines, uncovered)
+    }
+
+    fn row(row: i32, columns: i32) -> Vec<Coord> {
+        (0..columns).map(|column| Coord(row, column)).collect()
+    }
+
+    fn assert_probability(analysis: &Analysis, coord: Coord, expected: f64) {
+        let p = analysis.probability(coord).unwrap();
+        assert!((p - expected).abs() < 1e-9, "{:?}: {} instead of {}", coord, p, expected);
+    }
+
+    // 1 2 1 under five covered cells, which only the search can work out: _ * _ * _
+    fn one_two_one() -> Field {
+        board(Size(2, 5), &[Coord(0, 1), Coord(0, 3)], &row(1, 5))
+    }
+
+    #[test]
+    fn deduces_safe_cells_and_mines() {
+        let analysis = analyze(&one_two_one()).unwrap();
+        assert_eq!(analysis.safe, vec![Coord(0, 0), Coord(0, 2), Coord(0, 4)]);
+        assert_eq!(analysis.mines, vec![Coord(0, 1), Coord(0, 3)]);
+        assert_probability(&analysis, Coord(0, 0), 0.0);
+        assert_probability(&analysis, Coord(0, 1), 1.0);
+        assert_eq!(analysis.probability(Coord(1, 2)), None);
+    }
+
+    // a 1 on both cells of the second column: one mine among the four covered cells next to them
+    fn column_of_ones(mines: &[Coord]) -> Field {
+        board(Size(2, 4), mines, &[Coord(0, 1), Coord(1, 1)])
+    }
+
+    #[test]
+    fn counts_the_mines_left_for_the_interior() {
+        // the only mine is next to the ones, so the cells further away are safe
+        let analysis = analyze(&column_of_ones(&[Coord(0, 0)])).unwrap();
+        assert_eq!(analysis.safe, vec![Coord(0, 3), Coord(1, 3)]);
+        assert!(analysis.mines.is_empty());
+        assert_probability(&analysis, Coord(0, 0), 0.25);
+        assert_probability(&analysis, Coord(1, 2), 0.25);
+    }
+
+    #[test]
+    fn weighs_probabilities_by_the_ways_to_fill_the_interior() {
+        let analysis = analyze(&column_of_ones(&[Coord(0, 0), Coord(0, 3)])).unwrap();
+        assert!(analysis.safe.is_empty() && analysis.mines.is_empty());
+        assert_probability(&analysis, Coord(1, 0), 0.25);
+        assert_probability(&analysis, Coord(1, 3), 0.5);
+        assert_eq!(analysis.safest(), Some((Coord(0, 0), 0.25)));
+    }
+
+    #[test]
+    fn rearranges_the_mines_uniformly() {
+        use rand::SeedableRng;
+        let field = column_of_ones(&[Coord(0, 0)]);
+        let candidates = [Coord(0, 0), Coord(1, 0), Coord(0, 2), Coord(1, 2)];
+        let mut counts = [0; 4];
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..4000 {
+            let mines = rearrange(&field, Coord(0, 3), false, &mut rng).unwrap();
+            assert_eq!(mines.len(), 1);
+            counts[candidates.iter().position(|&c| c == mines[0]).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&count| (850..1150).contains(&count)), "{:?}", counts);
+    }
+
+    #[test]
+    fn falls_back_to_single_numbers_without_budget() {
+        // nothing follows from any one of 1 2 1 on its own
+        let analysis = analyze_within(&one_two_one(), &mut 0).unwrap();
+        assert!(analysis.safe.is_empty() && analysis.mines.is_empty());
+        assert_probability(&analysis, Coord(0, 2), 0.4);
+
+        // while a 2 with only two covered cells next to it gives them away
+        let field = board(Size(2, 3), &[Coord(0, 0), Coord(1, 0)], &[Coord(1, 2)]);
+        let analysis = analyze_within(&field, &mut 0).unwrap();
+        assert_eq!(analysis.mines, vec![Coord(0, 0), Coord(1, 0)]);
+        assert_probability(&analysis, Coord(1, 0), 1.0);
+    }
+
+    #[test]
+    fn knows_when_a_board_needs_no_guessing() {
+        let field = board(Size(3, 3), &[Coord(0, 0)], &[]);
+        assert!(is_solvable(&field, Coord(2, 2), &mut SEARCH_BUDGET.clone()));
+        // opening up from the other end leaves a mine in one of the last two cells
+        let field = board(Size(2, 4), &[Coord(0, 3)], &[]);
+        assert!(!is_solvable(&field, Coord(1, 0), &mut SEARCH_BUDGET.clone()));
+        assert!(!is_solvable(&field, Coord(1, 0), &mut 0));
+    }
+}
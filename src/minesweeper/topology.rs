@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use crate::coord::{Coord, Size};
+
+// Which cells count as neighbors, i.e. which mines a number counts
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Kernel {
+    // the 8 surrounding cells
+    Square,
+    // hexagonal cells in offset rows, every odd row being shifted half a cell to the right
+    Hex,
+    // the 4 cells sharing an edge
+    Orthogonal,
+    // the 8 cells a knight's move away
+    Knight,
+    // the 24 other cells of the surrounding 5x5 square
+    Extended,
+}
+
+impl Kernel {
+    const HEX_EVEN_ROW: [Coord; 6] = [
+        Coord(-1, -1),
+        Coord(-1, 0),
+        Coord(0, -1),
+        Coord(0, 1),
+        Coord(1, -1),
+        Coord(1, 0),
+    ];
+    const HEX_ODD_ROW: [Coord; 6] = [
+        Coord(-1, 0),
+        Coord(-1, 1),
+        Coord(0, -1),
+        Coord(0, 1),
+        Coord(1, 0),
+        Coord(1, 1),
+    ];
+    const ORTHOGONAL: [Coord; 4] = [
+        Coord(-1, 0),
+        Coord(0, -1),
+        Coord(0, 1),
+        Coord(1, 0),
+    ];
+    const KNIGHT: [Coord; 8] = [
+        Coord(-2, -1),
+        Coord(-2, 1),
+        Coord(-1, -2),
+        Coord(-1, 2),
+        Coord(1, -2),
+        Coord(1, 2),
+        Coord(2, -1),
+        Coord(2, 1),
+    ];
+    const EXTENDED: [Coord; 24] = [
+        Coord(-2, -2), Coord(-2, -1), Coord(-2, 0), Coord(-2, 1), Coord(-2, 2),
+        Coord(-1, -2), Coord(-1, -1), Coord(-1, 0), Coord(-1, 1), Coord(-1, 2),
+        Coord(0, -2), Coord(0, -1), Coord(0, 1), Coord(0, 2),
+        Coord(1, -2), Coord(1, -1), Coord(1, 0), Coord(1, 1), Coord(1, 2),
+        Coord(2, -2), Coord(2, -1), Coord(2, 0), Coord(2, 1), Coord(2, 2),
+    ];
+
+    fn directions(self, coord: Coord) -> &'static [Coord] {
+        match self {
+            Kernel::Square => &Coord::DIRECTIONS,
+            Kernel::Hex if coord.0 % 2 == 0 => &Self::HEX_EVEN_ROW,
+            Kernel::Hex => &Self::HEX_ODD_ROW,
+            Kernel::Orthogonal => &Self::ORTHOGONAL,
+            Kernel::Knight => &Self::KNIGHT,
+            Kernel::Extended => &Self::EXTENDED,
+        }
+    }
+}
+
+impl FromStr for Kernel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "square" => Ok(Self::Square),
+            "hex" => Ok(Self::Hex),
+            "orthogonal" => Ok(Self::Orthogonal),
+            "knight" => Ok(Self::Knight),
+            "extended" => Ok(Self::Extended),
+            _ => Err(()),
+        }
+    }
+}
+
+// What lies beyond the edges of the board
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Edges {
+    Bounded,
+    // the left and right edges are joined
+    Cylinder,
+    // the top and bottom edges are joined as well
+    Torus,
+}
+
+impl FromStr for Edges {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bounded" => Ok(Self::Bounded),
+            "cylinder" => Ok(Self::Cylinder),
+            "torus" => Ok(Self::Torus),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Topology {
+    pub kernel: Kernel,
+    pub edges: Edges,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Self { kernel: Kernel::Square, edges: Edges::Bounded }
+    }
+}
+
+impl Topology {
+    // Joining the top and bottom of a hex grid only lines up if it has an even number of rows
+    pub fn fit_rows(self, rows: u32) -> u32 {
+        if self.kernel == Kernel::Hex && self.edges == Edges::Torus {
+            rows + rows % 2
+        } else {
+            rows
+        }
+    }
+
+    fn wrap(self, coord: Coord, size: Size) -> Option<Coord> {
+        let Coord(mut row, mut column) = coord;
+        if self.edges != Edges::Bounded {
+            column = column.rem_euclid(size.1 as _);
+        }
+        if self.edges == Edges::Torus {
+            row = row.rem_euclid(size.0 as _);
+        }
+        let coord = Coord(row, column);
+        size.contains(coord).then_some(coord)
+    }
+
+    // On small wrapped boards different directions may lead to the same cell, or back to the
+    // center; each neighbor is only listed once
+    pub fn neighborhood(self, center: Coord, size: Size) -> impl Iterator<Item=Coord> {
+        let mut neighbors = Vec::with_capacity(self.kernel.directions(center).len());
+        for &d in self.kernel.directions(center) {
+            if let Some(c) = self.wrap(center + d, size) {
+                if c != center && !neighbors.contains(&c) {
+                    neighbors.push(c);
+                }
+            }
+        }
+        neighbors.into_iter()
+    }
+
+    pub fn describe(self) -> Vec<&'static str> {
+        let kernel = match self.kernel {
+            Kernel::Square => None,
+            Kernel::Hex => Some("hex"),
+            Kernel::Orthogonal => Some("orthogonal neighbors"),
+            Kernel::Knight => Some("knight's move neighbors"),
+            Kernel::Extended => Some("5x5 neighbors"),
+        };
+        let edges = match self.edges {
+            Edges::Bounded => None,
+            Edges::Cylinder => Some("cylinder"),
+            Edges::Torus => Some("torus"),
+        };
+        kernel.into_iter().chain(edges).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNELS: [Kernel; 5] = [Kernel::Square, Kernel::Hex, Kernel::Orthogonal, Kernel::Knight, Kernel::Extended];
+    const EDGES: [Edges; 3] = [Edges::Bounded, Edges::Cylinder, Edges::Torus];
+
+    fn neighbors(topology: Topology, center: Coord, size: Size) -> Vec<Coord> {
+        let mut neighbors: Vec<_> = topology.neighborhood(center, size).collect();
+        neighbors.sort_by_key(|&Coord(row, column)| (row, column));
+        neighbors
+    }
+
+    #[test]
+    fn neighbors_are_mutual() {
+        for &kernel in &KERNELS {
+            for &edges in &EDGES {
+                let topology = Topology { kernel, edges };
+                for &(rows, columns) in &[(2, 2), (3, 3), (4, 5), (7, 6)] {
+                    let size = Size(topology.fit_rows(rows), columns);
+                    for a in size.valid_indices() {
+                        for b in topology.neighborhood(a, size) {
+                            assert!(b != a && topology.neighborhood(b, size).any(|c| c == a));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_every_direction_away_from_the_edges() {
+        let size = Size(10, 10);
+        for &(kernel, count) in &[(Kernel::Square, 8), (Kernel::Hex, 6), (Kernel::Orthogonal, 4),
+            (Kernel::Knight, 8), (Kernel::Extended, 24)] {
+            for &center in &[Coord(4, 4), Coord(5, 5)] {
+                let topology = Topology { kernel, edges: Edges::Bounded };
+                assert_eq!(topology.neighborhood(center, size).count(), count);
+            }
+        }
+    }
+
+    #[test]
+    fn wraps_around_joined_edges() {
+        let size = Size(4, 4);
+        let corner = Coord(0, 0);
+        let square = |edges| neighbors(Topology { kernel: Kernel::Square, edges }, corner, size);
+        assert_eq!(square(Edges::Bounded), vec![Coord(0, 1), Coord(1, 0), Coord(1, 1)]);
+        assert_eq!(square(Edges::Cylinder), vec![Coord(0, 1), Coord(0, 3), Coord(1, 0), Coord(1, 1), Coord(1, 3)]);
+        assert_eq!(square(Edges::Torus).len(), 8);
+        assert!(square(Edges::Torus).contains(&Coord(3, 3)));
+    }
+
+    #[test]
+    fn lists_each_neighbor_once_on_small_boards() {
+        // on two columns, left and right of a cell are the same cell
+        let topology = Topology { kernel: Kernel::Square, edges: Edges::Torus };
+        assert_eq!(neighbors(topology, Coord(0, 0), Size(2, 2)), vec![Coord(0, 1), Coord(1, 0), Coord(1, 1)]);
+    }
+
+    #[test]
+    fn hex_tori_have_an_even_number_of_rows() {
+        let hex = |edges| Topology { kernel: Kernel::Hex, edges };
+        assert_eq!(hex(Edges::Torus).fit_rows(7), 8);
+        assert_eq!(hex(Edges::Torus).fit_rows(8), 8);
+        assert_eq!(hex(Edges::Cylinder).fit_rows(7), 7);
+    }
+}
@@ -0,0 +1,134 @@
+use telegram_bot::InlineKeyboardButton;
+
+use crate::game::{Coord, Size};
+use super::topology::Kernel;
+
+// callback data of the blank buttons offsetting rows on hex grids
+const PADDING: &str = "pad";
+// callback data of the arrows moving the view
+const UP: &str = "up";
+const DOWN: &str = "down";
+const LEFT: &str = "left";
+const RIGHT: &str = "right";
+
+// the most rows or columns a board may have
+pub const MAX_SIZE: u32 = 30;
+// boards with up to this many rows are shown whole
+pub const MAX_ROWS: u32 = 10;
+// larger ones through a view of this many rows, leaving room for the arrows under it
+const VIEW_ROWS: u32 = 8;
+
+// every row of a hex grid takes a button of padding
+pub fn max_columns(kernel: Kernel) -> u32 {
+    if kernel == Kernel::Hex { 7 } else { 8 }
+}
+
+// The part of a board that fits on an inline keyboard, which takes at most 8 buttons per row and
+// 100 in total.  Buttons call back with the coordinates of their cell on the whole board, so
+// clicks on an outdated view still land where they were meant to.
+#[derive(Copy, Clone)]
+pub struct Viewport {
+    // None for boards without edges
+    size: Option<Size>,
+    kernel: Kernel,
+    offset: Coord,
+    view: Size,
+}
+
+impl Viewport {
+    pub fn new(size: Size, kernel: Kernel) -> Self {
+        let columns = max_columns(kernel);
+        let view = if size.0 <= MAX_ROWS && size.1 <= columns {
+            size
+        } else {
+            Size(size.0.min(VIEW_ROWS), size.1.min(columns))
+        };
+        Self { size: Some(size), kernel, offset: Coord(0, 0), view }
+    }
+
+    // a view of a square grid going on forever, centered on its origin
+    pub fn unbounded() -> Self {
+        let view = Size(VIEW_ROWS, max_columns(Kernel::Square));
+        let offset = Coord(-((view.0 / 2) as i32), -((view.1 / 2) as i32));
+        Self { size: None, kernel: Kernel::Square, offset, view }
+    }
+
+    fn scrolls(&self) -> bool {
+        self.size != Some(self.view)
+    }
+
+    // moves the view by half its size, returns true if it has moved
+    pub fn pan(&mut self, button: &str) -> bool {
+        let rows = (self.view.0 / 2) as i32;
+        let columns = (self.view.1 / 2) as i32;
+        let step = match button {
+            UP => Coord(-rows, 0),
+            DOWN => Coord(rows, 0),
+            LEFT => Coord(0, -columns),
+            RIGHT => Coord(0, columns),
+            _ => return false,
+        };
+        self.move_to(self.offset + step)
+    }
+
+    // centers the view on a cell that is out of sight, returns true if it has moved
+    pub fn show(&mut self, coord: Coord) -> bool {
+        if self.view.contains(coord - self.offset) {
+            return false;
+        }
+        self.move_to(coord - Coord((self.view.0 / 2) as _, (self.view.1 / 2) as _))
+    }
+
+    fn move_to(&mut self, offset: Coord) -> bool {
+        let offset = match self.size {
+            Some(size) => Coord(
+                offset.0.clamp(0, (size.0 - self.view.0) as _),
+                offset.1.clamp(0, (size.1 - self.view.1) as _),
+            ),
+            None => offset,
+        };
+        let moved = offset != self.offset;
+        self.offset = offset;
+        moved
+    }
+
+    // rows and columns count from 1 on bounded boards, and from the origin on unbounded ones
+    pub fn describe(&self) -> Option<String> {
+        let first = self.offset + if self.size.is_some() { Coord(1, 1) } else { Coord(0, 0) };
+        let last = first + Coord(self.view.0 as i32 - 1, self.view.1 as i32 - 1);
+        self.scrolls().then(|| format!("Showing rows {} to {}, columns {} to {}", first.0, last.0, first.1, last.1))
+    }
+
+    // the buttons of the cells in view, and the arrows if the board doesn't fit
+    pub fn render(&self, text: impl Fn(Coord) -> String) -> Vec<Vec<InlineKeyboardButton>> {
+        let mut keyboard = (0..self.view.0 as i32)
+            .map(|i| {
+                let row = self.offset.0 + i;
+                let mut buttons: Vec<_> = (0..self.view.1 as i32)
+                    .map(|j| {
+                        let coord = Coord(row, self.offset.1 + j);
+                        InlineKeyboardButton::callback(text(coord), format!("{} {}", coord.0, coord.1))
+                    })
+                    .collect();
+                if self.kernel == Kernel::Hex {
+                    // a whole button is the closest we get to shifting odd rows by half a cell
+                    let padding = InlineKeyboardButton::callback(" ", PADDING);
+                    if row % 2 == 1 {
+                        buttons.insert(0, padding);
+                    } else {
+                        buttons.push(padding);
+                    }
+                }
+                buttons
+            }).collect::<Vec<Vec<_>>>();
+        if self.scrolls() {
+            keyboard.push(vec![
+                InlineKeyboardButton::callback("⬅️", LEFT),
+                InlineKeyboardButton::callback("⬆️", UP),
+                InlineKeyboardButton::callback("⬇️", DOWN),
+                InlineKeyboardButton::callback("➡️", RIGHT),
+            ]);
+        }
+        keyboard
+    }
+}
@@ -86,6 +86,8 @@ impl game::Game for Game {
                 update_text: Some(self.get_text()),
                 update_board: Some(self.to_inline_keyboard()),
                 game_end: self.board.game_over,
+                new_game: None,
+                reply: None,
             }
         )
     }
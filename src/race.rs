@@ -0,0 +1,308 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use telegram_bot::{InlineKeyboardButton, InlineKeyboardMarkup, User, UserId};
+
+use crate::game::{Coord, Game, InteractResult};
+use crate::grid_game::{GameState, GridGame};
+use crate::minesweeper::game::Game as MinesweeperGame;
+
+const ROWS: u32 = 10;
+const COLUMNS: u32 = 8;
+const MINES: u32 = 12;
+// how long runners may join once the race is posted, and how long it may last in all
+const JOIN_TIME: Duration = Duration::from_secs(2 * 60);
+const TIME_LIMIT: Duration = Duration::from_secs(30 * 60);
+
+struct Runner {
+    user: UserId,
+    name: String,
+    cleared: u32,
+    state: GameState,
+    // from when the runner joined, as the opening is there to see from then on
+    time: Option<Duration>,
+}
+
+// A race on identical boards.  Everyone who joins plays their own board in a private chat with the
+// bot, while the message in the group where the race was posted follows how they are doing.
+pub struct Race {
+    pub id: u32,
+    seed: u64,
+    created: Instant,
+    runners: Vec<Runner>,
+    // whether the group's message is out of date
+    changed: bool,
+}
+
+impl Race {
+    pub fn new() -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            id: rand::random(),
+            seed: rand::random(),
+            created: Instant::now(),
+            runners: Vec::new(),
+            changed: false,
+        }))
+    }
+
+    fn is_open(&self) -> bool {
+        self.created.elapsed() < JOIN_TIME
+    }
+
+    // once joining has closed and everyone is done, or out of time
+    pub fn is_over(&self) -> bool {
+        !self.is_open() && self.runners.iter().all(|r| r.state != GameState::Normal)
+            || self.created.elapsed() >= TIME_LIMIT
+    }
+
+    // returns None if the user has already joined, or joining has closed
+    pub fn join(race: &Arc<Mutex<Self>>, user: &User) -> Option<(RaceGame, String, InlineKeyboardMarkup)> {
+        let mut this = race.lock().unwrap();
+        if !this.is_open() || this.runners.iter().any(|r| r.user == user.id) {
+            return None;
+        }
+        // a board with an opening is the same for everyone, wherever they click first
+        let game = MinesweeperGame::with_opening(ROWS, COLUMNS, MINES, this.seed);
+        this.runners.push(Runner {
+            user: user.id,
+            name: user.username.to_owned().unwrap_or_else(|| user.first_name.to_owned()),
+            cleared: game.cleared(),
+            state: GameState::Normal,
+            time: None,
+        });
+        this.changed = true;
+        let game = RaceGame { game, runner: this.runners.len() - 1, race: race.clone(), started: Instant::now() };
+        drop(this);
+        let text = game.get_text();
+        let inline_keyboard = game.game.to_inline_keyboard();
+        Some((game, text, inline_keyboard))
+    }
+
+    fn get_text(&self) -> String {
+        let mut text = format!("🏁 Minesweeper race, {} x {} with {} mines\n", ROWS, COLUMNS, MINES);
+        if self.is_over() {
+            text += "The race is over.\n";
+        } else if self.is_open() {
+            text += "Join to get your board in a private chat with me, the clock starts as soon as you join.\n";
+        } else {
+            text += "Joining has closed.\n";
+        }
+        if self.runners.is_empty() {
+            return text + "\nNo one has joined yet.";
+        }
+        let cells = ROWS * COLUMNS - MINES;
+        let order = |r: &Runner| match r.state {
+            GameState::Solved => (0, r.time),
+            GameState::Normal => (1, None),
+            GameState::GameOver => (2, None),
+        };
+        let standings = self.runners.iter()
+            .sorted_by(|a, b| order(a).cmp(&order(b)).then(b.cleared.cmp(&a.cleared)))
+            .enumerate()
+            .map(|(i, r)| match r.state {
+                GameState::Solved => format!("{}. {} - 🏆 {:.1}s", i + 1, r.name, r.time.unwrap_or_default().as_secs_f64()),
+                GameState::Normal if self.is_over() => format!("{} - ⏱ {} / {} cells", r.name, r.cleared, cells),
+                GameState::Normal => format!("{} - {} / {} cells", r.name, r.cleared, cells),
+                GameState::GameOver => format!("{} - 💥 {} / {} cells", r.name, r.cleared, cells),
+            })
+            .join("\n");
+        text + "\n" + standings.as_str()
+    }
+}
+
+// The race's message in the group, which is only ever updated on ticks so that a busy race doesn't
+// run into the limits on editing messages
+pub struct RaceBoard {
+    race: Arc<Mutex<Race>>,
+    link: String,
+    open: bool,
+}
+
+impl RaceBoard {
+    // runners join through a link starting a private chat with the bot
+    pub fn create(race: &Arc<Mutex<Race>>, bot_name: &str) -> (Self, String, InlineKeyboardMarkup) {
+        let board = Self {
+            link: format!("https://t.me/{}?start=race{}", bot_name, race.lock().unwrap().id),
+            race: race.clone(),
+            open: true,
+        };
+        let text = board.race.lock().unwrap().get_text();
+        let inline_keyboard = board.to_inline_keyboard();
+        (board, text, inline_keyboard)
+    }
+
+    fn to_inline_keyboard(&self) -> InlineKeyboardMarkup {
+        let mut keyboard = Vec::new();
+        if self.open {
+            keyboard.push(vec![InlineKeyboardButton::url("🏁 Join", &self.link)]);
+        }
+        keyboard.into()
+    }
+}
+
+impl Game for RaceBoard {
+    fn interact(&mut self, _coord: Coord, _user: &User) -> Option<InteractResult> {
+        None
+    }
+
+    fn tick(&mut self) -> Option<InteractResult> {
+        let mut race = self.race.lock().unwrap();
+        // joining closes and the race runs out of time without anyone clicking
+        let open = race.is_open();
+        let over = race.is_over();
+        if !race.changed && open == self.open && !over {
+            return None;
+        }
+        race.changed = false;
+        self.open = open;
+        Some(InteractResult {
+            update_text: Some(race.get_text()),
+            update_board: Some(self.to_inline_keyboard()),
+            game_end: over,
+            new_game: None,
+            reply: None,
+        })
+    }
+}
+
+// One runner's board
+pub struct RaceGame {
+    game: MinesweeperGame,
+    race: Arc<Mutex<Race>>,
+    runner: usize,
+    started: Instant,
+}
+
+impl RaceGame {
+    fn get_text(&self) -> String {
+        let race = self.race.lock().unwrap();
+        let mut text = format!("🏁 Race for {}\n{}", race.runners[self.runner].name, self.game.get_text());
+        if race.is_over() && race.runners[self.runner].state == GameState::Normal {
+            text += "\n⏱ The race is over.";
+        }
+        text
+    }
+}
+
+impl Game for RaceGame {
+    fn interact(&mut self, coord: Coord, user: &User) -> Option<InteractResult> {
+        let mut race = self.race.lock().unwrap();
+        let over = race.is_over();
+        let runner = &mut race.runners[self.runner];
+        if over || user.id != runner.user || runner.state != GameState::Normal || !self.game.interact(coord, &runner.name) {
+            return None;
+        }
+        runner.cleared = self.game.cleared();
+        runner.state = self.game.get_state();
+        let game_end = runner.state != GameState::Normal;
+        if game_end {
+            runner.time = Some(self.started.elapsed());
+        }
+        race.changed = true;
+        drop(race);
+        Some(InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.game.to_inline_keyboard()),
+            game_end,
+            new_game: None,
+            reply: None,
+        })
+    }
+
+    fn replay(&self) -> Option<Box<dyn Game>> {
+        self.game.replay()
+    }
+
+    fn press(&mut self, button: &str, user: &User) -> Option<InteractResult> {
+        let mut race = self.race.lock().unwrap();
+        if race.is_over() || user.id != race.runners[self.runner].user || !self.game.press(button) {
+            return None;
+        }
+        race.changed = true;
+        drop(race);
+        Some(InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.game.to_inline_keyboard()),
+            game_end: false,
+            new_game: None,
+            reply: None,
+        })
+    }
+
+    // boards still being played when the race ends are closed
+    fn tick(&mut self) -> Option<InteractResult> {
+        let over = self.race.lock().unwrap().is_over();
+        if !over {
+            return None;
+        }
+        Some(InteractResult {
+            update_text: Some(self.get_text()),
+            update_board: Some(self.game.to_inline_keyboard()),
+            game_end: true,
+            new_game: None,
+            reply: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runner(id: i64, name: &str, cleared: u32, state: GameState, time: Option<u64>) -> Runner {
+        Runner {
+            user: UserId::new(id),
+            name: name.to_owned(),
+            cleared,
+            state,
+            time: time.map(Duration::from_secs),
+        }
+    }
+
+    // a race whose joining closed a second ago
+    fn race(runners: Vec<Runner>) -> Race {
+        Race {
+            id: 0,
+            seed: 0,
+            created: Instant::now() - JOIN_TIME - Duration::from_secs(1),
+            runners,
+            changed: false,
+        }
+    }
+
+    fn standings(race: &Race) -> Vec<String> {
+        race.get_text().lines().skip(3).map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn ranks_finishers_by_time_then_everyone_by_cells_cleared() {
+        let race = race(vec![
+            runner(1, "slow", 68, GameState::Solved, Some(90)),
+            runner(2, "boom", 40, GameState::GameOver, None),
+            runner(3, "playing", 20, GameState::Normal, None),
+            runner(4, "fast", 68, GameState::Solved, Some(45)),
+            runner(5, "ahead", 50, GameState::Normal, None),
+        ]);
+        assert!(!race.is_over());
+        assert_eq!(standings(&race), vec![
+            "1. fast - 🏆 45.0s",
+            "2. slow - 🏆 90.0s",
+            "ahead - 50 / 68 cells",
+            "playing - 20 / 68 cells",
+            "boom - 💥 40 / 68 cells",
+        ]);
+    }
+
+    #[test]
+    fn ends_once_everyone_is_done() {
+        let mut race = race(vec![
+            runner(1, "fast", 68, GameState::Solved, Some(45)),
+            runner(2, "playing", 20, GameState::Normal, None),
+        ]);
+        assert!(!race.is_over());
+        race.runners[1].state = GameState::GameOver;
+        assert!(race.is_over());
+        assert!(race.get_text().contains("The race is over."));
+    }
+}